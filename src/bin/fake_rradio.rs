@@ -0,0 +1,44 @@
+//! fake_rradio pretends to be rradio, so that the screen driver can be tried out without rradio or a radio.
+//! It sends the scenario in the file specified to every connection, eg
+//! `cargo run --bin fake_rradio -- scenarios/typical.toml` then `cargo run -- --rradio 127.0.0.1:8002`
+//! The endpoint to listen on can be changed with `--listen <endpoint>`, eg `--listen unix:/tmp/rradio.sock`.
+
+use rradio_lcd_driver::endpoint::{Endpoint, RRadioListener};
+use rradio_lcd_driver::fake_rradio::Scenario;
+
+#[tokio::main(flavor = "current_thread")]
+async fn main() -> Result<(), anyhow::Error> {
+    let mut endpoint = Endpoint::default();
+    let mut scenario_path = None;
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--listen" => {
+                endpoint = args
+                    .next()
+                    .ok_or_else(|| anyhow::anyhow!("--listen needs to be followed by an endpoint"))?
+                    .parse()
+                    .map_err(anyhow::Error::msg)?
+            }
+            _ => scenario_path = Some(arg),
+        }
+    }
+    let scenario_path = scenario_path.ok_or_else(|| {
+        anyhow::anyhow!("usage: fake_rradio [--listen <endpoint>] <scenario.toml>")
+    })?;
+    let scenario = Scenario::load(&scenario_path)?;
+
+    let listener = RRadioListener::bind(&endpoint).await?;
+    println!("fake rradio listening on {endpoint} & playing {scenario_path}");
+    loop {
+        let mut stream = listener.accept().await?;
+        println!("screen driver connected");
+        match scenario.play(&mut stream).await {
+            Ok(commands) => println!(
+                "screen driver disconnected after sending {} commands",
+                commands.len()
+            ),
+            Err(error) => println!("lost the connection to the screen driver: {error}"),
+        }
+    }
+}
@@ -0,0 +1,25 @@
+//! lcdctl sends a request to the screen driver over its control socket, & prints the answer, eg
+//! `lcdctl post --priority 5 --duration 60 Backing up` puts a message on the bottom two lines for a minute,
+//! `lcdctl clear 1` clears it again, `lcdctl frame` prints what is on the screen, & `lcdctl redraw` redraws it.
+//! `lcdctl shutdown` & `lcdctl reboot` ask on the screen whether to go ahead, which has to be answered there.
+
+use rradio_lcd_driver::control::{request_from_command_line, send_request, Response};
+
+#[tokio::main(flavor = "current_thread")]
+async fn main() -> Result<(), anyhow::Error> {
+    let (socket_path, request) =
+        request_from_command_line(std::env::args().skip(1)).map_err(anyhow::Error::msg)?;
+    match send_request(&socket_path, &request).await? {
+        Response::Posted { id } => println!("posted message {id}"),
+        Response::Cleared { count } => println!("cleared {count} messages"),
+        Response::Frame { lines } => {
+            for line in lines {
+                println!("|{line}|");
+            }
+        }
+        Response::Redrawn => println!("redrawn"),
+        Response::Asked => println!("asked on the screen"),
+        Response::Error { message } => anyhow::bail!(message),
+    }
+    Ok(())
+}
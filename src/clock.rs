@@ -0,0 +1,98 @@
+use chrono::{DateTime, FixedOffset, Local, TimeZone, Utc};
+use tokio::time::Instant;
+
+/// Clock tells the driver the time. There are two kinds of time: the instant, which is used for timing, eg how long a title
+/// has been shown before it scrolls, & the local date & time, which is used for showing the time of day.
+/// The driver only asks the clock, so a test can use a clock that it controls. The clock is shared with the control socket,
+/// which answers requests on tasks of its own.
+pub trait Clock: Send + Sync + std::fmt::Debug {
+    /// now returns the instant used for timing. It never goes backwards, even if the date & time are changed.
+    fn now(&self) -> Instant;
+    /// date_time returns the local date & time, including the offset from UTC, which changes with daylight saving time
+    fn date_time(&self) -> DateTime<FixedOffset>;
+}
+
+/// SystemClock is the real time
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+
+    fn date_time(&self) -> DateTime<FixedOffset> {
+        Local::now().into()
+    }
+}
+
+/// ManualClock is a clock for testing. Its date & time start at the moment given, & move on with tokio's clock,
+/// which a test can pause & advance with tokio::time::pause & tokio::time::advance. As both kinds of time come from tokio,
+/// they stay in step with each other, & with the timers the driver waits on.
+/// The offset from UTC can change at given moments, as it does when the clocks go forward or back.
+#[derive(Debug, Clone)]
+pub struct ManualClock {
+    start: Instant,
+    start_date_time: DateTime<Utc>,
+    offsets: Vec<(DateTime<Utc>, FixedOffset)>, // each offset & the moment it starts, in time order
+}
+
+impl ManualClock {
+    /// new returns a clock which starts now at the date & time given
+    pub fn new(start_date_time: DateTime<FixedOffset>) -> Self {
+        let start_date_time_utc = start_date_time.with_timezone(&Utc);
+        ManualClock {
+            start: Instant::now(),
+            start_date_time: start_date_time_utc,
+            offsets: vec![(start_date_time_utc, *start_date_time.offset())],
+        }
+    }
+
+    /// with_offset_from changes the offset from UTC at the moment given, eg when the clocks go forward for summer time
+    pub fn with_offset_from(mut self, from: DateTime<Utc>, offset: FixedOffset) -> Self {
+        self.offsets.push((from, offset));
+        self.offsets.sort_by_key(|(from, _offset)| *from);
+        self
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+
+    fn date_time(&self) -> DateTime<FixedOffset> {
+        let utc = self.start_date_time
+            + chrono::Duration::from_std(Instant::now().saturating_duration_since(self.start))
+                .unwrap_or_default();
+        let offset = self
+            .offsets
+            .iter()
+            .rev()
+            .find(|(from, _offset)| *from <= utc)
+            .map_or(self.offsets[0].1, |(_from, offset)| *offset);
+        offset.from_utc_datetime(&utc.naive_utc())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[tokio::test(start_paused = true)]
+    async fn the_manual_clock_goes_forward_for_summer_time() {
+        let hour = 3600;
+        let gmt = FixedOffset::east_opt(0).unwrap();
+        let bst = FixedOffset::east_opt(hour).unwrap();
+        // in the UK the clocks go forward at 1am GMT on the last Sunday in March
+        let clock = ManualClock::new(gmt.with_ymd_and_hms(2026, 3, 29, 0, 59, 58).unwrap())
+            .with_offset_from(Utc.with_ymd_and_hms(2026, 3, 29, 1, 0, 0).unwrap(), bst);
+        let start = clock.now();
+        assert_eq!(clock.date_time().format("%H:%M:%S").to_string(), "00:59:58");
+
+        tokio::time::advance(Duration::from_secs(3)).await;
+        assert_eq!(clock.date_time().format("%H:%M:%S").to_string(), "02:00:01");
+        assert_eq!(clock.now() - start, Duration::from_secs(3)); // the instant is not affected by the clocks changing
+    }
+}
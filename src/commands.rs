@@ -0,0 +1,86 @@
+use rradio_messages::Command;
+use std::time::Duration;
+use tokio::io::{AsyncWrite, AsyncWriteExt};
+use tokio::sync::mpsc;
+use tokio::time::Instant;
+
+use crate::events::Overlay;
+use crate::localisation::{text, MessageId};
+
+/// how long the message saying that a command could not be sent is shown for
+const COMMAND_FAILED_OVERLAY_TIME: Duration = Duration::from_secs(5);
+
+/// CommandSender sends commands to rradio, eg from a button. The commands are passed to the driver,
+/// which sends them over its connection to rradio. It can be cloned, so that each control can have its own.
+#[derive(Debug, Clone)]
+pub struct CommandSender(mpsc::UnboundedSender<Command>);
+
+impl CommandSender {
+    /// send queues the command to be sent to rradio. Commands sent while the driver is not connected to rradio are dropped,
+    /// as by the time it reconnects they are probably out of date.
+    pub fn send(&self, command: Command) {
+        if let Err(error) = self.0.send(command) {
+            println!("The driver has stopped, so {:?} was not sent", error.0);
+        }
+    }
+}
+
+/// channel returns a sender for the controls & the receiver the driver takes the commands from
+pub fn channel() -> (CommandSender, mpsc::UnboundedReceiver<Command>) {
+    let (sender, receiver) = mpsc::unbounded_channel();
+    (CommandSender(sender), receiver)
+}
+
+/// encode encodes the command in the same way as rradio encodes events, ie postcard with COBS, which ends with a zero
+pub fn encode(command: &Command) -> Vec<u8> {
+    postcard::to_stdvec_cobs(command).expect("a command can always be encoded")
+}
+
+/// write_command sends the command over the connection to rradio. If it fails, the problem is printed
+/// & the overlay saying so is returned, so that the person pressing the button knows that nothing will happen.
+pub async fn write_command(
+    connection: &mut (impl AsyncWrite + Unpin),
+    command: &Command,
+    now: Instant,
+) -> Option<Overlay> {
+    let written = match connection.write_all(&encode(command)).await {
+        Ok(()) => connection.flush().await,
+        Err(error) => Err(error),
+    };
+    match written {
+        Ok(()) => None,
+        Err(error) => {
+            println!("Failed to send {:?} to rradio: {}", command, error);
+            Some(Overlay {
+                text: text(MessageId::CommandFailed, &[]),
+                until: now + COMMAND_FAILED_OVERLAY_TIME,
+            })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn a_failed_command_is_shown() {
+        let (mut rradio, mut screen_driver) = tokio::io::duplex(64);
+        let now = Instant::now();
+        assert_eq!(
+            write_command(&mut screen_driver, &Command::VolumeUp, now).await,
+            None
+        );
+        let received =
+            crate::fake_rradio::read_command(&mut tokio::io::BufReader::new(&mut rradio))
+                .await
+                .unwrap();
+        assert!(matches!(received, Some(Command::VolumeUp)));
+
+        drop(rradio);
+        let overlay = write_command(&mut screen_driver, &Command::Eject, now)
+            .await
+            .unwrap();
+        assert_eq!(overlay.text, "Could not send the command to rradio");
+    }
+}
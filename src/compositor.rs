@@ -0,0 +1,236 @@
+//! The compositor, which builds the frame shown on the screen out of layers. Each thing that writes to the screen,
+//! eg what is playing, the state of the buffer, an error, a message about an event or the menu, draws into its own layer,
+//! which only covers the cells it writes to. The layers are put on top of one another in order of priority,
+//! so where two of them write to the same cell, the one with the higher priority wins, whatever order they were drawn in.
+//! A layer can expire, eg a message about an event, & then whatever it was covering is shown again.
+
+use tokio::time::Instant;
+
+use crate::lcd::{Frame, LineNum, NUM_CHARACTERS_PER_LINE};
+
+const NUM_LINES: usize = LineNum::ALL.len();
+
+/// Priority decides which layers go on top of which. Later variants go on top of earlier ones.
+#[derive(PartialEq, Eq, PartialOrd, Ord, Debug, Clone, Copy)]
+pub enum Priority {
+    Background, // what is playing, or the page being shown
+    Status,     // eg the state of the buffer, which goes in the space left by a short title
+    Splash,     // the station that has just started playing
+    Error,      // takes the whole screen, as it is more important than what was playing
+    Posted(u8), // a message from another program through the control socket, with the priority it was posted with
+    Overlay,    // a message about an event, or the key that has been pressed
+    Menu,       // takes the whole screen, as it is only open while someone is using it
+}
+
+/// LayerId is the producer of a layer. Each producer has one layer, which it replaces whenever it has something new to show,
+/// except the control socket, which has one for each message posted.
+#[derive(PartialEq, Eq, PartialOrd, Ord, Debug, Clone, Copy)]
+pub enum LayerId {
+    Playing,    // what is playing, or the page being shown
+    Buffer,     // the state of the gstreamer buffer
+    Progress,   // how far through the track the player is
+    Splash(u8), // one line of the splash, as each line goes a moment after the one above it
+    Error,
+    Posted(u64), // the id the message was given when posted
+    Volume,      // the volume in big digits while it is being changed
+    Overlay,
+    Menu,
+}
+
+/// Layer is what one producer writes to the screen. Cells that have not been written to are transparent,
+/// so the layers underneath show through them.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct Layer {
+    cells: [[Option<char>; NUM_CHARACTERS_PER_LINE]; NUM_LINES],
+}
+
+impl Default for Layer {
+    fn default() -> Self {
+        Layer {
+            cells: [[None; NUM_CHARACTERS_PER_LINE]; NUM_LINES],
+        }
+    }
+}
+
+impl Layer {
+    /// new returns a layer that is transparent everywhere
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// opaque returns a layer that covers the whole screen with the frame
+    pub fn opaque(frame: &Frame) -> Self {
+        let mut layer = Layer::new();
+        for line_number in LineNum::ALL {
+            layer.write(line_number, 0, &frame.line(line_number));
+        }
+        layer
+    }
+
+    /// write writes the string to the line starting at the specified column, like Frame::write.
+    /// Any characters that do not fit on the line are dropped.
+    pub fn write(&mut self, line_number: LineNum, column: usize, string: &str) {
+        let line = &mut self.cells[line_number.into_u16() as usize];
+        for (cell, one_char) in line.iter_mut().skip(column).zip(string.chars()) {
+            *cell = Some(one_char);
+        }
+    }
+
+    /// write_multiline writes exactly the specified number of characters starting at the beginning of the line, like Frame::write_multiline,
+    /// so all of them are covered, even where the string is too short & is padded with spaces
+    pub fn write_multiline(&mut self, line_number: LineNum, length: usize, string: &str) {
+        let start = line_number.into_u16() as usize * NUM_CHARACTERS_PER_LINE;
+        let mut chars = string.chars();
+        for position in start..(start + length).min(NUM_LINES * NUM_CHARACTERS_PER_LINE) {
+            self.cells[position / NUM_CHARACTERS_PER_LINE][position % NUM_CHARACTERS_PER_LINE] =
+                Some(chars.next().unwrap_or(' '));
+        }
+    }
+
+    /// draw writes the cells the layer covers over the frame
+    pub fn draw(&self, frame: &mut Frame) {
+        for (line_number, line) in LineNum::ALL.iter().zip(self.cells.iter()) {
+            for (column, cell) in line.iter().enumerate() {
+                if let Some(one_char) = cell {
+                    frame.write(*line_number, column, one_char.encode_utf8(&mut [0; 4]));
+                }
+            }
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Entry {
+    id: LayerId,
+    priority: Priority,
+    layer: Layer,
+    until: Option<Instant>, // None if the layer stays until it is replaced or removed
+}
+
+/// Compositor holds the layers that have not expired or been removed, & puts them together to make the frame
+#[derive(Debug, Default)]
+pub struct Compositor {
+    entries: Vec<Entry>,
+}
+
+impl Compositor {
+    /// set replaces the producer's layer, or adds it if the producer does not have one. If there is an until, the layer is
+    /// dropped at that time, & whatever it was covering is shown again.
+    pub fn set(&mut self, id: LayerId, priority: Priority, layer: Layer, until: Option<Instant>) {
+        self.remove(id);
+        self.entries.push(Entry {
+            id,
+            priority,
+            layer,
+            until,
+        });
+    }
+
+    /// remove removes the producer's layer, if it has one
+    pub fn remove(&mut self, id: LayerId) {
+        self.entries.retain(|entry| entry.id != id);
+    }
+
+    /// remove_if removes the layers of every producer the test is true for, eg every posted message
+    pub fn remove_if(&mut self, test: impl Fn(LayerId) -> bool) {
+        self.entries.retain(|entry| !test(entry.id));
+    }
+
+    /// is_showing is true if the producer has a layer that has not expired
+    pub fn is_showing(&self, id: LayerId, now: Instant) -> bool {
+        self.entries
+            .iter()
+            .any(|entry| entry.id == id && entry.until.map_or(true, |until| now < until))
+    }
+
+    /// next_expiry returns when the next layer expires, so that the screen can be redrawn without it, or None if none of them expire
+    pub fn next_expiry(&self) -> Option<Instant> {
+        self.entries.iter().filter_map(|entry| entry.until).min()
+    }
+
+    /// compose drops the layers that have expired, & returns the frame made by drawing the others from the lowest priority
+    /// to the highest. Layers with the same priority are drawn in the order of their ids, so the frame does not depend on
+    /// the order the layers were set in.
+    pub fn compose(&mut self, now: Instant) -> Frame {
+        self.entries
+            .retain(|entry| entry.until.map_or(true, |until| now < until));
+        self.entries.sort_by_key(|entry| (entry.priority, entry.id));
+        let mut frame = Frame::new();
+        for entry in &self.entries {
+            entry.layer.draw(&mut frame);
+        }
+        frame
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn line_layer(line_number: LineNum, string: &str) -> Layer {
+        let mut layer = Layer::new();
+        layer.write(line_number, 0, string);
+        layer
+    }
+
+    #[test]
+    fn the_highest_priority_is_on_top_whatever_the_order() {
+        let now = Instant::now();
+        let mut compositor = Compositor::default();
+        compositor.set(
+            LayerId::Overlay,
+            Priority::Overlay,
+            line_layer(LineNum::Line4, "Overlay"),
+            None,
+        );
+        compositor.set(
+            LayerId::Buffer,
+            Priority::Status,
+            line_layer(LineNum::Line4, "Buffer bar"),
+            None,
+        );
+        compositor.set(
+            LayerId::Playing,
+            Priority::Background,
+            line_layer(LineNum::Line4, "Second half of the title"),
+            None,
+        );
+        let frame = compositor.compose(now);
+        assert_eq!(frame.line(LineNum::Line4), "Overlaybarf of the t"); // the cells not covered show through
+
+        compositor.remove(LayerId::Overlay);
+        assert_eq!(
+            compositor.compose(now).line(LineNum::Line4),
+            "Buffer barf of the t"
+        );
+    }
+
+    #[test]
+    fn an_expired_layer_shows_what_was_underneath() {
+        let now = Instant::now();
+        let mut compositor = Compositor::default();
+        let mut error = Layer::new();
+        error.write_multiline(LineNum::Line1, NUM_CHARACTERS_PER_LINE * 4, "CD tray open");
+        compositor.set(LayerId::Error, Priority::Error, error, None);
+        let mut volume = Layer::new();
+        volume.write_multiline(LineNum::Line3, NUM_CHARACTERS_PER_LINE, "Vol 42");
+        compositor.set(
+            LayerId::Overlay,
+            Priority::Overlay,
+            volume,
+            Some(now + Duration::from_secs(2)),
+        );
+        assert_eq!(
+            compositor.compose(now).line(LineNum::Line3).trim_end(),
+            "Vol 42"
+        );
+        assert!(compositor.is_showing(LayerId::Overlay, now));
+
+        let later = now + Duration::from_secs(2);
+        assert!(!compositor.is_showing(LayerId::Overlay, later));
+        let frame = compositor.compose(later);
+        assert_eq!(frame.line(LineNum::Line1).trim_end(), "CD tray open");
+        assert_eq!(frame.line(LineNum::Line3).trim_end(), "");
+    }
+}
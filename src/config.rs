@@ -0,0 +1,160 @@
+use serde::Deserialize;
+use std::collections::HashMap;
+
+use crate::control::ControlConfig;
+use crate::endpoint::Endpoint;
+use crate::events::EventsConfig;
+use crate::input::evdev::EvdevConfig;
+use crate::input::gpio::GpioConfig;
+use crate::localisation::Locale;
+use crate::menu::MenuConfig;
+use crate::pages::{default_pages, PageConfig};
+use crate::progress::ProgressConfig;
+use crate::shutdown::ShutdownConfig;
+use crate::splash::SplashConfig;
+
+/// the file the configuration is read from. If it does not exist, the defaults are used.
+pub const CONFIG_FILE_PATH: &str = "lcd_config.toml";
+
+/// Config holds everything that can be set in the configuration file. Every field has a default,
+/// so the file only needs to contain the values that are wanted to be different from the defaults.
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    /// where rradio is listening, eg "192.168.1.20:8002" or "unix:/run/rradio.sock". It can be overridden with --rradio on the command line.
+    pub rradio: Endpoint,
+    /// the language used for the messages, eg "fr"
+    pub locale: Locale,
+    pub metadata: MetadataConfig,
+    pub station_names: StationNamesConfig,
+    /// the pages the display rotates through, in the order they are shown
+    pub pages: Vec<PageConfig>,
+    /// what is done when rradio sends each kind of event
+    pub events: EventsConfig,
+    /// the buttons & rotary encoders wired to the GPIO lines, if there are any
+    pub gpio: GpioConfig,
+    /// the IR remotes & USB keypads, if there are any
+    pub evdev: EvdevConfig,
+    pub menu: MenuConfig,
+    /// how the computer is shut down & rebooted from the menu
+    pub shutdown: ShutdownConfig,
+    /// the socket through which other programs can put messages on the screen
+    pub control: ControlConfig,
+    /// the splash shown when a station starts playing
+    pub splash: SplashConfig,
+    /// how the progress through the track is shown for CDs, USB sticks & Samba shares
+    pub progress: ProgressConfig,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            rradio: Endpoint::default(),
+            locale: Locale::default(),
+            metadata: MetadataConfig::default(),
+            station_names: StationNamesConfig::default(),
+            pages: default_pages(),
+            events: EventsConfig::default(),
+            gpio: GpioConfig::default(),
+            evdev: EvdevConfig::default(),
+            menu: MenuConfig::default(),
+            shutdown: ShutdownConfig::default(),
+            control: ControlConfig::default(),
+            splash: SplashConfig::default(),
+            progress: ProgressConfig::default(),
+        }
+    }
+}
+
+impl Config {
+    /// load reads the configuration from the specified file. If the file cannot be read or is not valid,
+    /// the problem is reported & the defaults are used, as a radio with a blank screen is of no use to anyone.
+    pub fn load(path: &str) -> Config {
+        match std::fs::read_to_string(path) {
+            Ok(config_as_string) => match toml::from_str(&config_as_string) {
+                Ok(config) => config,
+                Err(error) => {
+                    println!("Failed to parse the config file {path}, so using the defaults. The error was {error}");
+                    Config::default()
+                }
+            },
+            Err(error) => {
+                println!("Could not read the config file {path} ({error}), so using the defaults");
+                Config::default()
+            }
+        }
+    }
+}
+
+/// RewriteRule is a regular expression; any text it matches is replaced by `replacement`, which can refer to
+/// the groups captured by the pattern as $1, $2 etc. If the replacement is not specified, the matched text is removed.
+#[derive(Debug, Clone, Deserialize)]
+pub struct RewriteRule {
+    pub pattern: String,
+    #[serde(default)]
+    pub replacement: String,
+}
+
+impl RewriteRule {
+    pub fn new(pattern: &str, replacement: &str) -> Self {
+        RewriteRule {
+            pattern: pattern.to_string(),
+            replacement: replacement.to_string(),
+        }
+    }
+}
+
+/// MetadataConfig holds the rules used to tidy up the tags sent by internet streams
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+pub struct MetadataConfig {
+    /// rewrite rules that are applied in order to the title, artist, album & organisation
+    pub rewrite: Vec<RewriteRule>,
+    /// if a tag matches any of these patterns after rewriting it is ignored, eg adverts
+    pub discard: Vec<String>,
+}
+
+impl Default for MetadataConfig {
+    fn default() -> Self {
+        MetadataConfig {
+            rewrite: vec![
+                // some streams send the raw ICY metadata, eg "StreamTitle='Adele - Hello';StreamUrl='';"
+                RewriteRule::new(r"(?i)^\s*StreamTitle='(.*?)'?;?\s*(StreamUrl=.*)?$", "$1"),
+            ],
+            discard: vec![
+                r"(?i)^(advert(isement)?s?|commercials?( break)?|ad ?break)$".to_string(),
+                r"(?i)^(https?://|www\.)".to_string(), // a web address is not a title
+            ],
+        }
+    }
+}
+
+/// StationNamesConfig holds the rules used to tidy up the names of stations before they are shown, eg
+/// ```toml
+/// [station_names]
+/// rewrite = [ { pattern = "(?i)^HQ:\\s*" }, { pattern = " - Radio$", replacement = " R" } ]
+///
+/// [station_names.display_names]
+/// "07" = "Radio 4"
+/// ```
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+pub struct StationNamesConfig {
+    /// rewrite rules that are applied in order to station titles, the organisation & the other tags
+    pub rewrite: Vec<RewriteRule>,
+    /// names to show instead of the station title, keyed by the channel number
+    pub display_names: HashMap<String, String>,
+}
+
+impl Default for StationNamesConfig {
+    fn default() -> Self {
+        StationNamesConfig {
+            rewrite: vec![
+                RewriteRule::new(r"(?i)^\s*(QUIET|HQ):\s*", ""), // eg "QUIET: Radio 3" as it is obvious that it is quiet
+                RewriteRule::new(r"(?i)\s*\((AAC\+?|MP3|HE-AAC|FLAC)\)\s*$", ""), // eg "Jazz FM (AAC)"
+                RewriteRule::new(r"(?i)\s*-\s*\d+\s*k(bps)?\s*$", ""), // eg "Radio Paradise - 128k"
+            ],
+            display_names: HashMap::new(),
+        }
+    }
+}
@@ -0,0 +1,551 @@
+//! The control socket, through which other programs on the computer, eg backup scripts, update scripts & cron jobs,
+//! can put messages on the screen without stopping the driver. Each request is one line of JSON,
+//! & is answered with one line of JSON, eg
+//!     {"post":{"text":"Backing up","priority":5,"duration_secs":60,"region":"bottom"}}
+//!     {"posted":{"id":1}}
+//! Shutting down & rebooting can be asked for too, but as anyone on the computer can use the socket,
+//! they only open the question on the screen, which has to be answered with the buttons or the remote.
+//! The lcdctl program sends the requests from the command line, eg `lcdctl post --duration 60 Backing up`.
+
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{UnixListener, UnixStream};
+use tokio::sync::Notify;
+use tokio::time::Instant;
+
+use crate::clock::Clock;
+use crate::compositor::{Compositor, Layer, LayerId, Priority};
+use crate::input::{Action, ActionSender};
+use crate::lcd::{LineNum, SharedScreen, NUM_CHARACTERS_PER_LINE};
+
+/// the control socket used if the config file does not give one
+pub const DEFAULT_SOCKET_PATH: &str = "/tmp/rradio_lcd_driver.sock";
+/// the longest a message can be shown for, which is a week, as a message that is wanted for longer can be posted again
+pub const MAX_DURATION_SECS: u64 = 7 * 24 * 60 * 60;
+
+/// ControlConfig is the configuration of the control socket, eg
+/// ```toml
+/// [control]
+/// socket_path = "/run/rradio_lcd_driver.sock"
+/// ```
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct ControlConfig {
+    pub enabled: bool,
+    pub socket_path: PathBuf,
+}
+
+impl Default for ControlConfig {
+    fn default() -> Self {
+        ControlConfig {
+            enabled: true,
+            socket_path: PathBuf::from(DEFAULT_SOCKET_PATH),
+        }
+    }
+}
+
+/// Region is the part of the screen a message is written to
+#[derive(PartialEq, Eq, Debug, Clone, Copy, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Region {
+    All,
+    Top, // lines 1 & 2
+    #[default]
+    Bottom, // lines 3 & 4, where the driver puts its own messages, so that the station can still be seen
+    Line1,
+    Line2,
+    Line3,
+    Line4,
+}
+
+impl Region {
+    /// lines returns the first line of the region & the number of lines in it
+    fn lines(self) -> (LineNum, usize) {
+        match self {
+            Region::All => (LineNum::Line1, 4),
+            Region::Top => (LineNum::Line1, 2),
+            Region::Bottom => (LineNum::Line3, 2),
+            Region::Line1 => (LineNum::Line1, 1),
+            Region::Line2 => (LineNum::Line2, 1),
+            Region::Line3 => (LineNum::Line3, 1),
+            Region::Line4 => (LineNum::Line4, 1),
+        }
+    }
+}
+
+fn default_duration_secs() -> u64 {
+    10
+}
+
+/// Message is a message posted by another program
+#[derive(PartialEq, Eq, Debug, Clone, Serialize, Deserialize)]
+pub struct Message {
+    pub text: String,
+    /// where messages overlap, the one with the highest priority is shown
+    #[serde(default)]
+    pub priority: u8,
+    /// how long the message is shown for, unless it is cleared first
+    #[serde(default = "default_duration_secs")]
+    pub duration_secs: u64,
+    #[serde(default)]
+    pub region: Region,
+}
+
+/// Request is what is asked for over the control socket
+#[derive(PartialEq, Eq, Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Request {
+    Post(Message),
+    /// clears the message with the id returned when it was posted, or every message if there is no id
+    Clear {
+        id: Option<u64>,
+    },
+    /// asks for what is on the screen
+    Frame,
+    Redraw,
+    /// asks on the screen whether to shut the computer down
+    ShutDown,
+    /// asks on the screen whether to reboot the computer
+    Reboot,
+}
+
+/// Response is the answer to a request
+#[derive(PartialEq, Eq, Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Response {
+    Posted { id: u64 },
+    Cleared { count: usize },
+    Frame { lines: Vec<String> },
+    Redrawn,
+    Asked, // the question whether to shut down or reboot has been put on the screen
+    Error { message: String },
+}
+
+/// PostedMessages are the messages posted through the control socket that have not expired or been cleared
+#[derive(Debug, Default)]
+pub struct PostedMessages {
+    last_id: u64,
+    messages: Vec<PostedMessage>, // in the order they were posted
+}
+
+#[derive(Debug)]
+struct PostedMessage {
+    id: u64,
+    message: Message,
+    until: Instant,
+}
+
+impl PostedMessages {
+    /// post adds the message, & returns the id that can be used to clear it, or why the message cannot be posted
+    pub fn post(&mut self, message: Message, now: Instant) -> Result<u64, String> {
+        if message.duration_secs > MAX_DURATION_SECS {
+            return Err(format!(
+                "A message cannot be shown for {} seconds, as the longest is {} seconds",
+                message.duration_secs, MAX_DURATION_SECS
+            ));
+        }
+        self.last_id += 1;
+        let until = now + Duration::from_secs(message.duration_secs);
+        self.messages.push(PostedMessage {
+            id: self.last_id,
+            message,
+            until,
+        });
+        Ok(self.last_id)
+    }
+
+    /// clear removes the message with the id, or every message if the id is None, & returns how many were removed
+    pub fn clear(&mut self, id: Option<u64>) -> usize {
+        let count_before = self.messages.len();
+        self.messages
+            .retain(|posted| id.is_some_and(|id| posted.id != id));
+        count_before - self.messages.len()
+    }
+
+    /// draw forgets the messages that have expired, & replaces the layers of the posted messages in the compositor with a layer
+    /// for each of the others. The one with the highest priority ends up on top, & the latest of those with the same priority,
+    /// as the ids go up.
+    pub fn draw(&mut self, compositor: &mut Compositor, now: Instant) {
+        self.messages.retain(|posted| now < posted.until);
+        compositor.remove_if(|id| matches!(id, LayerId::Posted(_)));
+        for posted in &self.messages {
+            let (first_line, line_count) = posted.message.region.lines();
+            let mut layer = Layer::new();
+            layer.write_multiline(
+                first_line,
+                NUM_CHARACTERS_PER_LINE * line_count,
+                &posted.message.text,
+            );
+            compositor.set(
+                LayerId::Posted(posted.id),
+                Priority::Posted(posted.message.priority),
+                layer,
+                Some(posted.until),
+            );
+        }
+    }
+}
+
+/// Control is what the control socket shares with the driver: the posted messages, which the driver draws,
+/// what is on the screen, & ways of asking the driver to redraw the screen & to ask whether to shut down or reboot
+#[derive(Debug, Clone)]
+pub struct Control {
+    pub messages: Arc<Mutex<PostedMessages>>,
+    pub screen: SharedScreen,
+    pub redraw: Arc<Notify>,
+    pub clock: Arc<dyn Clock>, // the driver's clock, so that messages expire when the driver thinks they should
+    pub actions: ActionSender,
+}
+
+impl Control {
+    /// handle does what the request asks, & returns the answer
+    pub fn handle(&self, request: Request) -> Response {
+        let now = self.clock.now();
+        let response = match request {
+            Request::Post(message) => match self.messages.lock().unwrap().post(message, now) {
+                Ok(id) => Response::Posted { id },
+                Err(message) => return Response::Error { message },
+            },
+            Request::Clear { id } => Response::Cleared {
+                count: self.messages.lock().unwrap().clear(id),
+            },
+            Request::Frame => {
+                let screen = self.screen.lock().unwrap();
+                return Response::Frame {
+                    lines: LineNum::ALL
+                        .iter()
+                        .map(|&line_number| screen.line(line_number))
+                        .collect(),
+                };
+            }
+            Request::Redraw => Response::Redrawn,
+            // never straight to powering off, as anyone can use the socket
+            Request::ShutDown => {
+                self.actions.send(Action::ShutDown);
+                return Response::Asked;
+            }
+            Request::Reboot => {
+                self.actions.send(Action::Reboot);
+                return Response::Asked;
+            }
+        };
+        self.redraw.notify_one();
+        response
+    }
+}
+
+/// bind starts listening on the control socket, which anyone on the computer can write to.
+/// A socket left behind by an earlier run is removed first.
+pub fn bind(socket_path: &Path) -> anyhow::Result<UnixListener> {
+    use std::os::unix::fs::PermissionsExt;
+
+    let _ = std::fs::remove_file(socket_path);
+    let listener = UnixListener::bind(socket_path)
+        .with_context(|| format!("Failed to listen on {}", socket_path.display()))?;
+    std::fs::set_permissions(socket_path, std::fs::Permissions::from_mode(0o666))
+        .with_context(|| format!("Failed to let everyone use {}", socket_path.display()))?;
+    Ok(listener)
+}
+
+/// serve answers the requests on each connection to the control socket, until the socket fails
+pub async fn serve(listener: UnixListener, control: Control) {
+    loop {
+        match listener.accept().await {
+            Ok((stream, _address)) => {
+                tokio::spawn(answer_requests(stream, control.clone()));
+            }
+            Err(error) => {
+                println!("Failed to accept a connection to the control socket, so no longer listening: {error}");
+                return;
+            }
+        }
+    }
+}
+
+/// answer_requests answers each line of the connection, until the other end closes it
+async fn answer_requests(stream: UnixStream, control: Control) {
+    let (from_client, mut to_client) = stream.into_split();
+    let mut lines = BufReader::new(from_client).lines();
+    loop {
+        let line = match lines.next_line().await {
+            Ok(Some(line)) => line,
+            Ok(None) => return,
+            Err(error) => {
+                println!("Failed to read from the control socket: {error}");
+                return;
+            }
+        };
+        let response = match serde_json::from_str(&line) {
+            Ok(request) => control.handle(request),
+            Err(error) => Response::Error {
+                message: format!("{:?} is not a valid request: {}", line, error),
+            },
+        };
+        let mut encoded =
+            serde_json::to_string(&response).expect("a response can always be encoded");
+        encoded.push('\n');
+        if let Err(error) = to_client.write_all(encoded.as_bytes()).await {
+            println!("Failed to answer on the control socket: {error}");
+            return;
+        }
+    }
+}
+
+/// send_request sends the request to the driver over the control socket, & returns the answer
+pub async fn send_request(socket_path: &Path, request: &Request) -> anyhow::Result<Response> {
+    let stream = UnixStream::connect(socket_path).await.with_context(|| {
+        format!(
+            "Failed to connect to {}; is the screen driver running?",
+            socket_path.display()
+        )
+    })?;
+    let (from_driver, mut to_driver) = stream.into_split();
+    let mut encoded = serde_json::to_string(request)?;
+    encoded.push('\n');
+    to_driver.write_all(encoded.as_bytes()).await?;
+    let line = BufReader::new(from_driver)
+        .lines()
+        .next_line()
+        .await?
+        .context("The screen driver closed the connection without answering")?;
+    Ok(serde_json::from_str(&line)?)
+}
+
+/// request_from_command_line returns the control socket & the request given on the command line of lcdctl, eg
+/// `post --priority 5 --duration 60 --region top Backing up`, `clear 3`, `clear`, `frame`, `redraw`, `shutdown` or `reboot`,
+/// any of which can start with `--socket <path>`
+pub fn request_from_command_line(
+    mut args: impl Iterator<Item = String>,
+) -> Result<(PathBuf, Request), String> {
+    let mut socket_path = PathBuf::from(DEFAULT_SOCKET_PATH);
+    let mut message = Message {
+        text: String::new(),
+        priority: 0,
+        duration_secs: default_duration_secs(),
+        region: Region::default(),
+    };
+    let mut words = Vec::new();
+    let mut request_name = None;
+    while let Some(arg) = args.next() {
+        let mut value = |what: &str| {
+            args.next()
+                .ok_or_else(|| format!("{} needs to be followed by {}", arg, what))
+        };
+        match arg.as_str() {
+            "--socket" => socket_path = PathBuf::from(value("a path")?),
+            "--priority" => {
+                let priority = value("a number")?;
+                message.priority = priority
+                    .parse()
+                    .map_err(|_| format!("\"{}\" is not a priority from 0 to 255", priority))?;
+            }
+            "--duration" => {
+                let duration = value("a number of seconds")?;
+                message.duration_secs = duration
+                    .parse()
+                    .map_err(|_| format!("\"{}\" is not a number of seconds", duration))?;
+            }
+            "--region" => {
+                let region = value("a region")?;
+                message.region = serde_json::from_value(serde_json::Value::String(region.clone()))
+                    .map_err(|_| {
+                        format!(
+                            "\"{}\" is not one of all, top, bottom, line1, line2, line3 or line4",
+                            region
+                        )
+                    })?;
+            }
+            _ if request_name.is_none() => request_name = Some(arg),
+            _ => words.push(arg),
+        }
+    }
+    let request = match request_name.as_deref() {
+        Some("post") if !words.is_empty() => {
+            message.text = words.join(" ");
+            Request::Post(message)
+        }
+        Some("post") => return Err("post needs the text of the message".to_string()),
+        Some("clear") => Request::Clear {
+            id: match words.first() {
+                Some(id) => Some(id.parse().map_err(|_| format!("\"{}\" is not the id of a message", id))?),
+                None => None,
+            },
+        },
+        Some("frame") => Request::Frame,
+        Some("redraw") => Request::Redraw,
+        Some("shutdown") => Request::ShutDown,
+        Some("reboot") => Request::Reboot,
+        _ => {
+            return Err(
+                "usage: lcdctl [--socket <path>] post [--priority <0-255>] [--duration <seconds>] [--region <region>] <text> | clear [<id>] | frame | redraw | shutdown | reboot"
+                    .to_string(),
+            )
+        }
+    };
+    Ok((socket_path, request))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::SystemClock;
+    use crate::input;
+    use crate::lcd::Frame;
+
+    /// composed returns the frame made by the posted messages on their own
+    fn composed(messages: &mut PostedMessages, now: Instant) -> Frame {
+        let mut compositor = Compositor::default();
+        messages.draw(&mut compositor, now);
+        compositor.compose(now)
+    }
+
+    fn message(text: &str, priority: u8, region: Region) -> Message {
+        Message {
+            text: text.to_string(),
+            priority,
+            duration_secs: 10,
+            region,
+        }
+    }
+
+    #[test]
+    fn the_message_with_the_highest_priority_is_on_top() {
+        let now = Instant::now();
+        let mut messages = PostedMessages::default();
+        messages
+            .post(message("Backing up", 5, Region::Bottom), now)
+            .unwrap();
+        let update = messages
+            .post(message("Updating", 1, Region::Line3), now)
+            .unwrap();
+        let frame = composed(&mut messages, now);
+        assert_eq!(frame.line(LineNum::Line3).trim_end(), "Backing up");
+
+        messages
+            .post(message("Low battery", 5, Region::Line4), now)
+            .unwrap();
+        let frame = composed(&mut messages, now);
+        assert_eq!(frame.line(LineNum::Line4).trim_end(), "Low battery"); // the latest of the same priority
+
+        assert_eq!(messages.clear(Some(update)), 1);
+        let frame = composed(&mut messages, now + Duration::from_secs(10)); // all expired
+        assert_eq!(frame, Frame::new());
+        assert_eq!(messages.clear(None), 0);
+    }
+
+    #[test]
+    fn a_message_cannot_be_shown_for_ever() {
+        let control = Control {
+            messages: Arc::default(),
+            screen: SharedScreen::default(),
+            redraw: Arc::default(),
+            clock: Arc::new(SystemClock),
+            actions: input::channel().0,
+        };
+        let mut forever = message("Forever", 0, Region::Bottom);
+        forever.duration_secs = u64::MAX;
+        assert!(matches!(
+            control.handle(Request::Post(forever)),
+            Response::Error { .. }
+        ));
+        assert!(!control.messages.is_poisoned()); // so the driver can still draw the messages
+        assert_eq!(
+            control.handle(Request::Post(message("Backing up", 0, Region::Bottom))),
+            Response::Posted { id: 1 }
+        );
+    }
+
+    #[test]
+    fn requests_are_read_from_the_command_line() {
+        let request =
+            |args: &[&str]| request_from_command_line(args.iter().map(|arg| arg.to_string()));
+        assert_eq!(
+            request(&[
+                "post",
+                "--priority",
+                "5",
+                "--region",
+                "top",
+                "Backing",
+                "up"
+            ]),
+            Ok((
+                PathBuf::from(DEFAULT_SOCKET_PATH),
+                Request::Post(Message {
+                    text: "Backing up".to_string(),
+                    priority: 5,
+                    duration_secs: 10,
+                    region: Region::Top,
+                })
+            ))
+        );
+        assert_eq!(
+            request(&["--socket", "/tmp/lcd.sock", "clear", "3"]),
+            Ok((
+                PathBuf::from("/tmp/lcd.sock"),
+                Request::Clear { id: Some(3) }
+            ))
+        );
+        assert!(request(&["post"]).is_err());
+        assert!(request(&["post", "--region", "middle", "Hello"]).is_err());
+        assert_eq!(
+            request(&["shutdown"]),
+            Ok((PathBuf::from(DEFAULT_SOCKET_PATH), Request::ShutDown))
+        );
+        assert_eq!(
+            request(&["reboot"]),
+            Ok((PathBuf::from(DEFAULT_SOCKET_PATH), Request::Reboot))
+        );
+        assert!(request(&["dance"]).is_err());
+    }
+
+    #[test]
+    fn shutting_down_and_rebooting_are_only_asked_about() {
+        let (actions, mut received) = input::channel();
+        let control = Control {
+            messages: Arc::default(),
+            screen: SharedScreen::default(),
+            redraw: Arc::default(),
+            clock: Arc::new(SystemClock),
+            actions,
+        };
+        assert_eq!(control.handle(Request::ShutDown), Response::Asked);
+        assert_eq!(received.try_recv().unwrap().action, Action::ShutDown);
+        assert_eq!(control.handle(Request::Reboot), Response::Asked);
+        assert_eq!(received.try_recv().unwrap().action, Action::Reboot);
+    }
+
+    #[tokio::test]
+    async fn requests_are_answered_over_the_socket() {
+        let socket_path =
+            std::env::temp_dir().join(format!("rradio_lcd_control_{}.sock", std::process::id()));
+        let control = Control {
+            messages: Arc::default(),
+            screen: SharedScreen::default(),
+            redraw: Arc::default(),
+            clock: Arc::new(SystemClock),
+            actions: input::channel().0,
+        };
+        tokio::spawn(serve(bind(&socket_path).unwrap(), control.clone()));
+
+        let request = Request::Post(message("Backing up", 0, Region::Line2));
+        assert_eq!(
+            send_request(&socket_path, &request).await.unwrap(),
+            Response::Posted { id: 1 }
+        );
+        let frame = composed(&mut control.messages.lock().unwrap(), Instant::now());
+        assert_eq!(frame.line(LineNum::Line2).trim_end(), "Backing up");
+
+        match send_request(&socket_path, &Request::Frame).await.unwrap() {
+            Response::Frame { lines } => {
+                assert_eq!(lines, vec![" ".repeat(NUM_CHARACTERS_PER_LINE); 4])
+            }
+            other => panic!("expected the frame, got {:?}", other),
+        }
+        let _ = std::fs::remove_file(&socket_path);
+    }
+}
@@ -0,0 +1,522 @@
+use futures_util::{Stream, StreamExt};
+use rradio_messages::{Command, CurrentStation, Event};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::io::AsyncWrite;
+use tokio::sync::{mpsc, watch, Notify};
+
+use crate::clock::Clock;
+use crate::commands::{write_command, CommandSender};
+use crate::compositor::{Compositor, Layer, LayerId, Priority};
+use crate::config::Config;
+use crate::control::{Control, PostedMessages};
+use crate::endpoint::RRadioStream;
+use crate::error_list::ErrorState;
+use crate::events::{EventKind, EventsConfig, Overlay};
+use crate::get_local_ip_address::get_local_ip_address;
+use crate::input::{Action, ActionEvent, ActionSender, ChannelEntry};
+use crate::lcd::{set_brightness, Frame, Lc, LineNum};
+use crate::localisation::{text, MessageId};
+use crate::menu::{main_menu, Menu, MenuChoice, MenuConfig, MenuOutcome, Navigation};
+use crate::metadata::MetadataRules;
+use crate::pages::{page_lines, Page, PageInfo, PageRotation};
+use crate::player_model::PlayerModel;
+use crate::progress::{draw_progress, next_tick, ProgressConfig};
+use crate::recording::Recorder;
+use crate::render::{render, render_layers, volume_layer, Moment, SCROLL_PERIOD};
+use crate::shutdown::PowerOff;
+use crate::splash::{hide_splash, show_splash, SplashConfig};
+use crate::station_names::StationNames;
+use crate::supervisor::show_header_error;
+use crate::system_info::{take_readings, SystemReadings};
+
+/// how long the key pressed on a remote or keypad, or the channel being typed, is shown for
+const KEY_ECHO_TIME: Duration = Duration::from_millis(1500);
+/// how long the volume is shown in big digits after it has changed
+const VOLUME_OVERLAY_TIME: Duration = Duration::from_secs(2);
+
+/// Ending is why the driver stopped showing the events
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum Ending {
+    ConnectionClosed,    // by rradio, or the events ran out
+    ClosedWithoutEvents, // by rradio straight after the header, eg because it is failing to start
+    Reconnect,           // chosen from the menu
+    RestartDriver,       // chosen from the menu
+    PowerOff(PowerOff),  // chosen from the menu & confirmed
+}
+
+/// Driver shows the events sent by rradio on the screen. The events can come from rradio, from a fake rradio or from a recording.
+pub struct Driver {
+    pub lcd: Lc,
+    pub model: PlayerModel,
+    events_config: EventsConfig,
+    page_rotation: PageRotation,
+    compositor: Compositor, // the layers the frame on the screen is made from
+    recorder: Option<Recorder>,
+    clock: Arc<dyn Clock>,
+    command_sender: CommandSender,
+    commands: mpsc::UnboundedReceiver<Command>, // the commands to send to rradio
+    action_sender: ActionSender,
+    actions: mpsc::UnboundedReceiver<ActionEvent>, // the actions from the controls, eg buttons
+    channel_entry: ChannelEntry, // the digits of the channel being typed on a remote or keypad
+    menu_config: MenuConfig,
+    splash_config: SplashConfig,
+    progress_config: ProgressConfig,
+    menu: Option<Menu>,                 // None unless the menu is open
+    posted: Arc<Mutex<PostedMessages>>, // the messages from other programs, through the control socket
+    redraw: Arc<Notify>, // notified when the control socket needs the screen to be redrawn
+    /// the latest readings from this computer, which are taken in the background
+    readings: watch::Receiver<SystemReadings>,
+}
+
+impl Driver {
+    /// new returns a driver that shows the events on the screen, using the clock for timing & for showing the time of day.
+    /// It starts taking the system readings in the background, so it has to be called from inside the tokio runtime.
+    pub fn new(lcd: Lc, config: &Config, clock: Box<dyn Clock>) -> Self {
+        let model = PlayerModel::new(
+            MetadataRules::new(&config.metadata),
+            StationNames::new(&config.station_names),
+        );
+        let (readings_sender, readings) = watch::channel(SystemReadings::default());
+        tokio::spawn(take_readings(readings_sender));
+        let (command_sender, commands) = crate::commands::channel();
+        let (action_sender, actions) = crate::input::channel();
+        Driver {
+            lcd,
+            model,
+            events_config: config.events.clone(),
+            page_rotation: PageRotation::new(&config.pages, clock.now()),
+            compositor: Compositor::default(),
+            recorder: None,
+            clock: Arc::from(clock),
+            command_sender,
+            commands,
+            action_sender,
+            actions,
+            channel_entry: ChannelEntry::default(),
+            menu_config: config.menu.clone(),
+            splash_config: config.splash.clone(),
+            progress_config: config.progress.clone(),
+            menu: None,
+            posted: Arc::default(),
+            redraw: Arc::default(),
+            readings,
+        }
+    }
+
+    /// action_sender returns a sender for the actions from the controls, eg buttons, while the driver is connected to rradio
+    pub fn action_sender(&self) -> ActionSender {
+        self.action_sender.clone()
+    }
+
+    /// command_sender returns a sender for the commands to send to rradio while the driver is connected to it
+    pub fn command_sender(&self) -> CommandSender {
+        self.command_sender.clone()
+    }
+
+    /// control returns what the control socket needs to put messages on the screen & see what is on it
+    pub fn control(&mut self) -> Control {
+        Control {
+            messages: self.posted.clone(),
+            screen: self.lcd.share_screen(),
+            redraw: self.redraw.clone(),
+            clock: self.clock.clone(),
+            actions: self.action_sender.clone(),
+        }
+    }
+
+    /// clock returns the clock the driver uses, eg so that the progress of shutting down is timed by it too
+    pub fn clock(&self) -> Arc<dyn Clock> {
+        self.clock.clone()
+    }
+
+    /// record_to records every event from now on
+    pub fn record_to(&mut self, recorder: Recorder) {
+        self.recorder = Some(recorder);
+    }
+
+    /// show_connection shows what rradio sends over the connection until rradio closes it, or something else is chosen from the menu.
+    /// If the header sent by rradio is not accepted, the reason is shown on the screen & returned.
+    pub async fn show_connection(
+        &mut self,
+        stream: RRadioStream,
+    ) -> Result<Ending, rradio_messages::BadRRadioHeader> {
+        self.lcd.clear(); //clear out the error message
+        self.lcd
+            .write_ascii(LineNum::Line1, 0, get_local_ip_address().as_str());
+        // the connection goes both ways: rradio sends events one way & we send commands the other way
+        let (from_rradio, to_rradio) = tokio::io::split(stream);
+        // we have got a message, but we do not know if it is valid, so we try & decode it
+        match Event::decode_from_stream(tokio::io::BufReader::new(from_rradio)).await {
+            Ok(rradio_events) => Ok(self.show_events(rradio_events, to_rradio).await),
+            Err(header_error) => {
+                println!("Header mismatch {:?}", header_error);
+                show_header_error(&mut self.lcd, &header_error);
+                Err(header_error)
+            }
+        }
+    }
+
+    /// show_events shows the events until there are no more, ie rradio has closed the connection, or something else
+    /// is chosen from the menu, & sends the commands from the command senders to rradio. rradio starts by sending
+    /// its complete state, so anything from a previous connection is forgotten first.
+    pub async fn show_events<E: std::fmt::Debug>(
+        &mut self,
+        events: impl Stream<Item = Result<Event, E>>,
+        mut to_rradio: impl AsyncWrite + Unpin,
+    ) -> Ending {
+        tokio::pin!(events);
+        self.model.reset();
+        self.compositor
+            .remove_if(|id| matches!(id, LayerId::Overlay | LayerId::Volume | LayerId::Splash(_)));
+        self.menu = None;
+        while let Ok(command) = self.commands.try_recv() {
+            println!("Not connected to rradio, so {:?} was not sent", command);
+        }
+        while let Ok(action_event) = self.actions.try_recv() {
+            println!(
+                "Not connected to rradio, so {:?} was not done",
+                action_event.action
+            );
+        }
+        let redraw = self.redraw.clone(); // so that waiting for it does not borrow the whole driver
+        let mut last_scroll_time = self.clock.now(); // otherwise, after a long wait, we would time out straight away many times
+        let mut got_a_message = false; // so that a connection that is closed before rradio says anything is not counted as working
+
+        loop {
+            let error_state = self.model.errors.state(self.clock.now());
+            let timeout_time = if error_state == ErrorState::NoStation {
+                last_scroll_time + Duration::from_millis(200) // update the time of day every second
+            } else {
+                last_scroll_time + SCROLL_PERIOD // we are scrolling, so the timeout has to be right for scrolling
+            };
+
+            let next_expiry = self.compositor.next_expiry();
+            let next_tick = next_tick(&self.model, self.clock.now());
+
+            let next_event = tokio::select! {
+                next_event = tokio::time::timeout_at(timeout_time, events.next()) => next_event,
+                Some(command) = self.commands.recv() => {
+                    self.send_command(&mut to_rradio, command).await;
+                    self.show_screen();
+                    continue;
+                }
+                Some(action_event) = self.actions.recv() => {
+                    if let Some(ending) = self.do_action(&mut to_rradio, action_event).await {
+                        return ending;
+                    }
+                    self.show_screen();
+                    continue;
+                }
+                Ok(()) = self.readings.changed() => {
+                    self.model.system = self.readings.borrow_and_update().clone();
+                    self.show_screen();
+                    continue;
+                }
+                _ = redraw.notified() => {
+                    self.show_screen();
+                    continue;
+                }
+                // so that whatever an expired layer was covering is shown again straight away
+                _ = tokio::time::sleep_until(next_expiry.unwrap_or(timeout_time)), if next_expiry.is_some() => {
+                    self.show_screen();
+                    continue;
+                }
+                // so that the time through the track goes up every second, rather than only when rradio sends the position
+                _ = tokio::time::sleep_until(next_tick.unwrap_or(timeout_time)), if next_tick.is_some() => {
+                    self.show_screen();
+                    continue;
+                }
+            };
+            match next_event {
+                Ok(None) => {
+                    // rradio closes the connection when it shuts down. The overlay is left on the screen for the supervisor
+                    // to draw over, rather than waiting for it to expire, so that reconnecting is not held up.
+                    if let Some(closed_overlay) =
+                        self.events_config
+                            .handle(EventKind::ConnectionClosed, "", self.clock.now())
+                    {
+                        self.show_overlay(closed_overlay);
+                        self.show_screen();
+                    }
+                    return if got_a_message {
+                        Ending::ConnectionClosed
+                    } else {
+                        Ending::ClosedWithoutEvents
+                    };
+                }
+                Ok(Some(Ok(event))) => {
+                    got_a_message = true;
+                    let now = self.clock.now();
+                    if let Some(recorder) = &mut self.recorder {
+                        if let Err(error) = recorder.record(&event, now) {
+                            println!("Failed to record the event, so stopped recording: {error}");
+                            self.recorder = None;
+                        }
+                    }
+                    if let Some(event_overlay) = self.events_config.handle(
+                        EventKind::of(&event),
+                        &format!("{:?}", event),
+                        now,
+                    ) {
+                        self.show_overlay(event_overlay);
+                    }
+                    match event {
+                        Event::PlayerStateChanged(player_state_difference) => {
+                            let volume_before = (self.model.volume, self.model.is_muted);
+                            let station_changed = player_state_difference.current_station.is_some();
+                            let station_playing = matches!(
+                                player_state_difference.current_station,
+                                Some(CurrentStation::PlayingStation { .. })
+                            );
+                            self.model.apply(player_state_difference, now);
+                            if station_changed {
+                                hide_splash(&mut self.compositor);
+                                if station_playing && self.splash_config.enabled {
+                                    show_splash(
+                                        &mut self.compositor,
+                                        &self.model,
+                                        &self.splash_config,
+                                        now,
+                                    );
+                                }
+                            }
+                            // the volume is not shown big when rradio first says what it is, as it has not been changed
+                            if volume_before.0 >= 0
+                                && volume_before != (self.model.volume, self.model.is_muted)
+                            {
+                                self.compositor.set(
+                                    LayerId::Volume,
+                                    Priority::Overlay,
+                                    volume_layer(&self.model),
+                                    Some(now + VOLUME_OVERLAY_TIME),
+                                );
+                            }
+                        }
+                    }
+                }
+                Ok(Some(Err(decode_error))) => {
+                    got_a_message = true;
+                    if let Some(event_overlay) = self.events_config.handle(
+                        EventKind::Undecodable,
+                        &format!("{:?}", decode_error),
+                        self.clock.now(),
+                    ) {
+                        self.show_overlay(event_overlay);
+                    }
+                }
+                Err(_elapsed_message) => {
+                    last_scroll_time = timeout_time; // otherwise the timeout is in the past & we time out straight away, for ever
+                    self.model.errors.expire(self.clock.now());
+                }
+            }
+
+            self.show_screen();
+        }
+    }
+
+    /// send_command sends the command to rradio, & shows an overlay if it cannot be sent
+    async fn send_command(&mut self, to_rradio: &mut (impl AsyncWrite + Unpin), command: Command) {
+        if let Some(command_overlay) = write_command(to_rradio, &command, self.clock.now()).await {
+            self.show_overlay(command_overlay);
+        }
+    }
+
+    /// do_action carries out the action, either by sending rradio the command for it, or by changing the screen.
+    /// If the action comes with an echo, eg the name of the key pressed on a remote, it is shown for a moment.
+    /// While the menu is open, the actions move around the menu instead. If the driver is to stop, the reason is returned.
+    async fn do_action(
+        &mut self,
+        to_rradio: &mut (impl AsyncWrite + Unpin),
+        action_event: ActionEvent,
+    ) -> Option<Ending> {
+        let now = self.clock.now();
+        let action = action_event.action;
+        if let Some(menu) = &mut self.menu {
+            // the menu has its own cursor, so there is no echo
+            let outcome = match Navigation::from_action(action) {
+                Some(navigation) => menu.navigate(navigation, now),
+                None => return None,
+            };
+            return match outcome {
+                MenuOutcome::Open => None,
+                MenuOutcome::Closed => {
+                    self.menu = None;
+                    None
+                }
+                MenuOutcome::Chosen(choice) => self.do_menu_choice(to_rradio, choice).await,
+            };
+        }
+        if let Some(echo) = &action_event.echo {
+            self.show_overlay(Overlay {
+                text: text(MessageId::KeyEcho, &[echo]),
+                until: now + KEY_ECHO_TIME,
+            });
+        }
+        if let Some(command) = action.command(&self.model.channel) {
+            self.send_command(to_rradio, command).await;
+            return None;
+        }
+        match action {
+            Action::NextPage => self.page_rotation.show_next(now),
+            Action::ShutDown => {
+                self.compositor.remove(LayerId::Overlay);
+                self.menu = Some(Menu::confirm(PowerOff::ShutDown, &self.menu_config, now));
+            }
+            Action::Reboot => {
+                self.compositor.remove(LayerId::Overlay);
+                self.menu = Some(Menu::confirm(PowerOff::Reboot, &self.menu_config, now));
+            }
+            Action::Menu => {
+                let stations = self.model.station_names().named_channels();
+                let can_set_brightness = self.menu_config.brightness_file.is_some();
+                self.compositor.remove(LayerId::Overlay); // the menu takes up the whole screen
+                self.menu = Some(Menu::open(
+                    main_menu(stations, can_set_brightness),
+                    &self.menu_config,
+                    now,
+                ));
+            }
+            Action::Digit(digit) => {
+                let channel = self.channel_entry.digit(digit, now);
+                // the channel so far is more use than the name of the key
+                self.show_overlay(Overlay {
+                    text: text(MessageId::Station, &[&self.channel_entry.typed()]),
+                    until: now + KEY_ECHO_TIME,
+                });
+                if let Some(channel) = channel {
+                    self.send_command(to_rradio, Command::SetChannel(channel))
+                        .await;
+                }
+            }
+            _ => println!("{:?} is not done by the screen", action),
+        }
+        None
+    }
+
+    /// do_menu_choice does what was chosen from the menu. If the driver is to stop, the reason is returned.
+    async fn do_menu_choice(
+        &mut self,
+        to_rradio: &mut (impl AsyncWrite + Unpin),
+        choice: MenuChoice,
+    ) -> Option<Ending> {
+        if choice.closes_menu() {
+            self.menu = None;
+        }
+        match choice {
+            MenuChoice::SetChannel(channel) => {
+                self.send_command(to_rradio, Command::SetChannel(channel))
+                    .await
+            }
+            MenuChoice::Backlight(on) => self.lcd.set_backlight(on),
+            MenuChoice::Brightness(percent) => {
+                if let Some(brightness_file) = &self.menu_config.brightness_file {
+                    if let Err(error) = set_brightness(brightness_file, percent) {
+                        println!("{error:#}");
+                    }
+                }
+            }
+            MenuChoice::Layout(profile) => {
+                self.page_rotation.set_profile(profile, self.clock.now())
+            }
+            MenuChoice::Reconnect => return Some(Ending::Reconnect),
+            MenuChoice::RestartDriver => return Some(Ending::RestartDriver),
+            MenuChoice::PowerOff(power_off) => return Some(Ending::PowerOff(power_off)),
+        }
+        None
+    }
+
+    /// show_overlay shows the overlay on top of everything but the menu, until it expires
+    fn show_overlay(&mut self, overlay: Overlay) {
+        self.compositor.set(
+            LayerId::Overlay,
+            Priority::Overlay,
+            overlay.layer(),
+            Some(overlay.until),
+        );
+    }
+
+    /// show_screen shows the menu if it is open, otherwise the current page, or what is playing,
+    /// with the posted messages & the overlay on top, if there are any
+    fn show_screen(&mut self) {
+        let now = Moment::at(self.clock.as_ref());
+        if self
+            .menu
+            .as_ref()
+            .is_some_and(|menu| menu.has_timed_out(now.instant))
+        {
+            self.menu = None;
+        }
+        let mut cursor = None;
+        let menu_frame = self.menu.as_ref().map(|menu| match menu.showing() {
+            Some(page) => self.page_frame(page, &now),
+            None => {
+                cursor = Some(menu.cursor());
+                menu.frame()
+            }
+        });
+        match menu_frame {
+            Some(menu_frame) => self.compositor.set(
+                LayerId::Menu,
+                Priority::Menu,
+                Layer::opaque(&menu_frame),
+                None,
+            ),
+            None => {
+                // what is underneath is only brought up to date while the menu is closed, as the menu hides it
+                self.compositor.remove(LayerId::Menu);
+                let page = self
+                    .page_rotation
+                    .current_page(now.instant, self.model.is_idle(now.instant));
+                self.draw_page(page, &now);
+            }
+        }
+        self.posted
+            .lock()
+            .unwrap()
+            .draw(&mut self.compositor, now.instant);
+        let frame = self.compositor.compose(now.instant);
+        self.lcd.show_frame(&frame);
+        self.lcd.show_cursor(cursor);
+    }
+
+    /// page_info returns what the pages show, from the latest readings rather than new ones, so that drawing the screen is never held up
+    fn page_info(&self, now: &Moment) -> PageInfo<'_> {
+        PageInfo {
+            system: &self.model.system,
+            ping_status: self.model.ping_status,
+            now: now.date_time,
+        }
+    }
+
+    /// draw_page puts the page into the compositor, or the layers showing what is playing for the now playing page
+    fn draw_page(&mut self, page: Page, now: &Moment) {
+        match page_lines(page, &self.page_info(now)) {
+            Some(lines) => {
+                let page_layer = Layer::opaque(&Frame::from_lines(&lines));
+                self.compositor
+                    .set(LayerId::Playing, Priority::Background, page_layer, None);
+                self.compositor.remove_if(|id| {
+                    matches!(id, LayerId::Buffer | LayerId::Progress | LayerId::Error)
+                });
+            }
+            None => {
+                render_layers(&self.model, now, &mut self.compositor);
+                draw_progress(
+                    &mut self.compositor,
+                    &self.model,
+                    &self.progress_config,
+                    now.instant,
+                );
+            }
+        }
+    }
+
+    /// page_frame returns the page as it is now, or what is playing for the now playing page
+    fn page_frame(&self, page: Page, now: &Moment) -> Frame {
+        match page_lines(page, &self.page_info(now)) {
+            Some(lines) => Frame::from_lines(&lines),
+            None => render(&self.model, now),
+        }
+    }
+}
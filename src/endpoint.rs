@@ -0,0 +1,287 @@
+use serde::Deserialize;
+use std::convert::TryFrom;
+use std::io;
+use std::path::PathBuf;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+use tokio::net::{TcpListener, TcpStream, UnixListener, UnixStream};
+
+/// the port rradio listens on if none is specified
+pub const DEFAULT_PORT: u16 = 8002;
+
+/// Endpoint is where rradio is listening: either a TCP host & port, which can be on another computer,
+/// or a Unix domain socket on this computer. In the config file & on the command line it is written as
+/// "192.168.1.20:8002", "rradio-box.local" (using the default port) or "unix:/run/rradio.sock".
+#[derive(PartialEq, Eq, Debug, Clone, Deserialize)]
+#[serde(try_from = "String")]
+pub enum Endpoint {
+    Tcp { host: String, port: u16 },
+    Unix(PathBuf),
+}
+
+impl Default for Endpoint {
+    fn default() -> Self {
+        Endpoint::Tcp {
+            host: std::net::Ipv4Addr::LOCALHOST.to_string(),
+            port: DEFAULT_PORT,
+        }
+    }
+}
+
+impl std::str::FromStr for Endpoint {
+    type Err = String;
+
+    fn from_str(endpoint: &str) -> Result<Self, Self::Err> {
+        if let Some(path) = endpoint.strip_prefix("unix:") {
+            return Ok(Endpoint::Unix(PathBuf::from(path)));
+        }
+        if endpoint.starts_with('/') {
+            return Ok(Endpoint::Unix(PathBuf::from(endpoint))); // a path on its own can only be a socket
+        }
+        if !endpoint.starts_with('[') && endpoint.matches(':').count() > 1 {
+            // a bare IPv6 address, eg "fe80::1", which cannot have a port, as its last colon is part of the address
+            return Ok(Endpoint::Tcp {
+                host: endpoint.to_string(),
+                port: DEFAULT_PORT,
+            });
+        }
+        let (host, port) = match endpoint.rsplit_once(':') {
+            // a colon inside square brackets is part of an IPv6 address, not the separator before the port
+            Some((host, port)) if !port.ends_with(']') => (
+                host,
+                port.parse()
+                    .map_err(|_| format!("\"{}\" is not a valid port in \"{}\"", port, endpoint))?,
+            ),
+            _ => (endpoint, DEFAULT_PORT),
+        };
+        let host = host.trim_start_matches('[').trim_end_matches(']');
+        if host.is_empty() {
+            return Err(format!(
+                "\"{}\" does not say which computer rradio is on",
+                endpoint
+            ));
+        }
+        Ok(Endpoint::Tcp {
+            host: host.to_string(),
+            port,
+        })
+    }
+}
+
+impl TryFrom<String> for Endpoint {
+    type Error = String;
+
+    fn try_from(endpoint: String) -> Result<Self, Self::Error> {
+        endpoint.parse()
+    }
+}
+
+impl std::fmt::Display for Endpoint {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Endpoint::Tcp { host, port } if host.contains(':') => write!(f, "[{}]:{}", host, port),
+            Endpoint::Tcp { host, port } => write!(f, "{}:{}", host, port),
+            Endpoint::Unix(path) => write!(f, "unix:{}", path.display()),
+        }
+    }
+}
+
+impl Endpoint {
+    /// connect opens a connection to rradio
+    pub async fn connect(&self) -> io::Result<RRadioStream> {
+        Ok(match self {
+            Endpoint::Tcp { host, port } => {
+                RRadioStream::Tcp(TcpStream::connect((host.as_str(), *port)).await?)
+            }
+            Endpoint::Unix(path) => RRadioStream::Unix(UnixStream::connect(path).await?),
+        })
+    }
+}
+
+/// RRadioListener listens for connections on an endpoint, as rradio does. It is used by the fake rradio server.
+#[derive(Debug)]
+pub enum RRadioListener {
+    Tcp(TcpListener),
+    Unix(UnixListener),
+}
+
+impl RRadioListener {
+    /// bind starts listening on the endpoint. A Unix socket left behind by an earlier run is removed first.
+    pub async fn bind(endpoint: &Endpoint) -> io::Result<Self> {
+        Ok(match endpoint {
+            Endpoint::Tcp { host, port } => {
+                RRadioListener::Tcp(TcpListener::bind((host.as_str(), *port)).await?)
+            }
+            Endpoint::Unix(path) => {
+                let _ = std::fs::remove_file(path);
+                RRadioListener::Unix(UnixListener::bind(path)?)
+            }
+        })
+    }
+
+    /// accept waits for the next connection
+    pub async fn accept(&self) -> io::Result<RRadioStream> {
+        Ok(match self {
+            RRadioListener::Tcp(listener) => RRadioStream::Tcp(listener.accept().await?.0),
+            RRadioListener::Unix(listener) => RRadioStream::Unix(listener.accept().await?.0),
+        })
+    }
+}
+
+/// from_command_line returns the endpoint given by "--rradio <endpoint>" or "--rradio=<endpoint>" on the command line,
+/// None if it is not given, or an error if it is given but not valid
+pub fn from_command_line(
+    mut args: impl Iterator<Item = String>,
+) -> Option<Result<Endpoint, String>> {
+    while let Some(arg) = args.next() {
+        if arg == "--rradio" {
+            return Some(match args.next() {
+                Some(endpoint) => endpoint.parse(),
+                None => Err("--rradio needs to be followed by where rradio is".to_string()),
+            });
+        }
+        if let Some(endpoint) = arg.strip_prefix("--rradio=") {
+            return Some(endpoint.parse());
+        }
+    }
+    None
+}
+
+/// RRadioStream is a connection to rradio over either transport, so that the rest of the program does not need to know which is used
+#[derive(Debug)]
+pub enum RRadioStream {
+    Tcp(TcpStream),
+    Unix(UnixStream),
+}
+
+impl AsyncRead for RRadioStream {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        match self.get_mut() {
+            RRadioStream::Tcp(stream) => Pin::new(stream).poll_read(cx, buf),
+            RRadioStream::Unix(stream) => Pin::new(stream).poll_read(cx, buf),
+        }
+    }
+}
+
+impl AsyncWrite for RRadioStream {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        match self.get_mut() {
+            RRadioStream::Tcp(stream) => Pin::new(stream).poll_write(cx, buf),
+            RRadioStream::Unix(stream) => Pin::new(stream).poll_write(cx, buf),
+        }
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        match self.get_mut() {
+            RRadioStream::Tcp(stream) => Pin::new(stream).poll_flush(cx),
+            RRadioStream::Unix(stream) => Pin::new(stream).poll_flush(cx),
+        }
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        match self.get_mut() {
+            RRadioStream::Tcp(stream) => Pin::new(stream).poll_shutdown(cx),
+            RRadioStream::Unix(stream) => Pin::new(stream).poll_shutdown(cx),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::AsyncWriteExt;
+
+    #[test]
+    fn endpoints_are_parsed() {
+        let tcp = |host: &str, port| Endpoint::Tcp {
+            host: host.to_string(),
+            port,
+        };
+        assert_eq!("192.168.1.20:8003".parse(), Ok(tcp("192.168.1.20", 8003)));
+        assert_eq!(
+            "rradio-box.local".parse(),
+            Ok(tcp("rradio-box.local", DEFAULT_PORT))
+        );
+        assert_eq!("[::1]:8002".parse(), Ok(tcp("::1", 8002)));
+        assert_eq!("[::1]".parse(), Ok(tcp("::1", DEFAULT_PORT)));
+        assert_eq!("::1".parse(), Ok(tcp("::1", DEFAULT_PORT)));
+        assert_eq!("fe80::1".parse(), Ok(tcp("fe80::1", DEFAULT_PORT)));
+        assert_eq!("[fe80::1]:9000".parse(), Ok(tcp("fe80::1", 9000)));
+        assert_eq!(
+            "unix:/run/rradio.sock".parse(),
+            Ok(Endpoint::Unix(PathBuf::from("/run/rradio.sock")))
+        );
+        assert_eq!(
+            "/run/rradio.sock".parse(),
+            Ok(Endpoint::Unix(PathBuf::from("/run/rradio.sock")))
+        );
+        assert!("rradio-box:radio".parse::<Endpoint>().is_err());
+        assert!(":8002".parse::<Endpoint>().is_err());
+
+        assert_eq!(tcp("::1", 8002).to_string(), "[::1]:8002");
+        let args = |args: &[&str]| args.iter().map(|arg| arg.to_string()).collect::<Vec<_>>();
+        assert_eq!(
+            from_command_line(args(&["--rradio", "10.0.0.2"]).into_iter()),
+            Some(Ok(tcp("10.0.0.2", DEFAULT_PORT)))
+        );
+        assert_eq!(
+            from_command_line(args(&["--rradio=10.0.0.2:9000"]).into_iter()),
+            Some(Ok(tcp("10.0.0.2", 9000)))
+        );
+        assert_eq!(from_command_line(args(&[]).into_iter()), None);
+    }
+
+    /// decodes_header_from checks that the header sent by a stand-in for rradio can be decoded over the endpoint
+    async fn decodes_header_from(
+        endpoint: Endpoint,
+        accept: impl std::future::Future<Output = RRadioStream>,
+    ) {
+        let (client, mut server) = tokio::join!(endpoint.connect(), accept);
+        server
+            .write_all(rradio_messages::API_VERSION_HEADER.as_bytes())
+            .await
+            .unwrap();
+        let decoded =
+            rradio_messages::Event::decode_from_stream(tokio::io::BufReader::new(client.unwrap()))
+                .await;
+        assert!(
+            decoded.is_ok(),
+            "the header was not decoded over {}",
+            endpoint
+        );
+    }
+
+    #[tokio::test]
+    async fn rradio_can_be_reached_over_tcp() {
+        let listener = tokio::net::TcpListener::bind((std::net::Ipv4Addr::LOCALHOST, 0))
+            .await
+            .unwrap();
+        let endpoint = Endpoint::Tcp {
+            host: "localhost".to_string(),
+            port: listener.local_addr().unwrap().port(),
+        };
+        decodes_header_from(endpoint, async {
+            RRadioStream::Tcp(listener.accept().await.unwrap().0)
+        })
+        .await;
+    }
+
+    #[tokio::test]
+    async fn rradio_can_be_reached_over_a_unix_socket() {
+        let path =
+            std::env::temp_dir().join(format!("rradio_lcd_test_{}.sock", std::process::id()));
+        let endpoint = Endpoint::Unix(path.clone());
+        let listener = RRadioListener::bind(&endpoint).await.unwrap();
+        decodes_header_from(endpoint, async { listener.accept().await.unwrap() }).await;
+        std::fs::remove_file(&path).unwrap();
+    }
+}
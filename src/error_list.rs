@@ -0,0 +1,215 @@
+use std::time::Duration;
+use tokio::time::Instant;
+
+use crate::errors::ErrorDescription;
+
+/// how long a transient error, eg a gstreamer error, is shown if rradio does not tell us it has recovered
+pub const TRANSIENT_ERROR_LIFETIME: Duration = Duration::from_secs(30);
+
+/// an enum of all the possible error states including those supplied by rradio as enums, gstreamer errors and the error as a string
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum ErrorState {
+    NotKnown,
+    NoError,
+    NoStation,
+    CdError,
+    CdEjectError,
+    MountError,
+    UsbOrSambaError,
+    GStreamerError,
+    ProgrammerError,
+    UPnPError,
+}
+
+/// Severity is how serious an error is. When there is more than one error, the most severe is shown.
+#[derive(PartialEq, Eq, PartialOrd, Ord, Debug, Clone, Copy)]
+pub enum Severity {
+    Warning,  // eg the channel asked for does not exist; the user can just ask for another
+    Error,    // eg there is no CD in the drive, which the user can fix
+    Critical, // eg a bad station file, which needs a programmer or the person who set up the radio
+}
+
+impl ErrorState {
+    /// severity returns how serious the error is
+    pub fn severity(self) -> Severity {
+        match self {
+            ErrorState::NoError | ErrorState::NoStation => Severity::Warning,
+            ErrorState::CdError
+            | ErrorState::CdEjectError
+            | ErrorState::MountError
+            | ErrorState::UsbOrSambaError
+            | ErrorState::GStreamerError
+            | ErrorState::UPnPError => Severity::Error,
+            ErrorState::NotKnown | ErrorState::ProgrammerError => Severity::Critical,
+        }
+    }
+
+    /// lifetime returns how long the error is shown for, or None if it is shown until rradio tells us it has gone.
+    /// gstreamer errors are transient, as rradio does not always tell us when it has recovered from them.
+    pub fn lifetime(self) -> Option<Duration> {
+        match self {
+            ErrorState::GStreamerError => Some(TRANSIENT_ERROR_LIFETIME),
+            _ => None,
+        }
+    }
+
+    /// is_station_error is true for the errors that come from trying to play a station,
+    /// which are cleared as soon as another station is tried
+    pub fn is_station_error(self) -> bool {
+        !matches!(self, ErrorState::GStreamerError | ErrorState::NoError)
+    }
+}
+
+/// ReportedError is one error in the error list, together with when it was reported
+#[derive(PartialEq, Debug, Clone)]
+pub struct ReportedError {
+    pub description: ErrorDescription,
+    pub first_reported: Instant,
+    pub last_reported: Instant,
+    pub report_count: u32, // how many times the same error has been reported
+    pub expiry_time: Option<Instant>,
+}
+
+impl ReportedError {
+    /// is_active is true if the error has not expired
+    pub fn is_active(&self, now: Instant) -> bool {
+        self.expiry_time
+            .map_or(true, |expiry_time| now < expiry_time)
+    }
+}
+
+/// ErrorList holds all the errors that are currently active. An error is removed when rradio shows that it has recovered,
+/// or when it expires if it is a transient error.
+#[derive(PartialEq, Debug, Clone, Default)]
+pub struct ErrorList {
+    errors: Vec<ReportedError>,
+}
+
+impl ErrorList {
+    pub fn new() -> ErrorList {
+        ErrorList::default()
+    }
+
+    /// report adds the error to the list. If the same error is already in the list, it is not added again,
+    /// but the time it was last reported is updated, which also delays its expiry.
+    pub fn report(&mut self, description: ErrorDescription, now: Instant) {
+        let expiry_time = description.state.lifetime().map(|lifetime| now + lifetime);
+        match self
+            .errors
+            .iter_mut()
+            .find(|error| error.description == description)
+        {
+            Some(error) => {
+                error.last_reported = now;
+                error.report_count += 1;
+                error.expiry_time = expiry_time;
+            }
+            None => self.errors.push(ReportedError {
+                description,
+                first_reported: now,
+                last_reported: now,
+                report_count: 1,
+                expiry_time,
+            }),
+        }
+    }
+
+    /// clear removes all the errors of the specified state
+    pub fn clear(&mut self, state: ErrorState) {
+        self.errors.retain(|error| error.description.state != state);
+    }
+
+    /// clear_station_errors removes all the errors that came from trying to play a station
+    pub fn clear_station_errors(&mut self) {
+        self.errors
+            .retain(|error| !error.description.state.is_station_error());
+    }
+
+    /// expire removes the errors that have expired
+    pub fn expire(&mut self, now: Instant) {
+        self.errors.retain(|error| error.is_active(now));
+    }
+
+    /// active returns the errors that have not expired, in the order they were first reported
+    pub fn active(&self, now: Instant) -> impl Iterator<Item = &ReportedError> {
+        self.errors.iter().filter(move |error| error.is_active(now))
+    }
+
+    /// most_important returns the error to show, which is the most severe active error, & of those the most recently reported
+    pub fn most_important(&self, now: Instant) -> Option<&ReportedError> {
+        self.active(now)
+            .max_by_key(|error| (error.description.state.severity(), error.last_reported))
+    }
+
+    /// state returns the state of the most important error, or NoError if there are no active errors
+    pub fn state(&self, now: Instant) -> ErrorState {
+        self.most_important(now)
+            .map_or(ErrorState::NoError, |error| error.description.state)
+    }
+
+    /// other_count returns how many active errors there are besides the most important one
+    pub fn other_count(&self, now: Instant) -> usize {
+        self.active(now).count().saturating_sub(1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn error(state: ErrorState, headline: &str) -> ErrorDescription {
+        ErrorDescription {
+            state,
+            headline: headline.to_string(),
+            explanation: String::new(),
+            remedy: String::new(),
+        }
+    }
+
+    #[test]
+    fn repeated_errors_are_only_listed_once() {
+        let now = Instant::now();
+        let mut errors = ErrorList::new();
+        errors.report(error(ErrorState::CdError, "No CD"), now);
+        errors.report(
+            error(ErrorState::CdError, "No CD"),
+            now + Duration::from_secs(1),
+        );
+        let listed: Vec<_> = errors.active(now).collect();
+        assert_eq!(listed.len(), 1);
+        assert_eq!(listed[0].report_count, 2);
+        assert_eq!(listed[0].first_reported, now);
+        assert_eq!(listed[0].last_reported, now + Duration::from_secs(1));
+    }
+
+    #[test]
+    fn the_most_severe_error_is_shown_and_the_others_counted() {
+        let now = Instant::now();
+        let mut errors = ErrorList::new();
+        errors.report(error(ErrorState::ProgrammerError, "Bad station 3"), now);
+        errors.report(
+            error(ErrorState::GStreamerError, "Pipeline error"),
+            now + Duration::from_secs(1),
+        );
+        assert_eq!(errors.state(now), ErrorState::ProgrammerError);
+        assert_eq!(errors.other_count(now), 1);
+
+        errors.clear_station_errors();
+        assert_eq!(errors.state(now), ErrorState::GStreamerError);
+        assert_eq!(errors.other_count(now), 0);
+    }
+
+    #[test]
+    fn transient_errors_expire() {
+        let now = Instant::now();
+        let mut errors = ErrorList::new();
+        errors.report(error(ErrorState::GStreamerError, "Pipeline error"), now);
+        errors.report(error(ErrorState::NoStation, "No station 42"), now);
+        let later = now + TRANSIENT_ERROR_LIFETIME;
+        assert_eq!(errors.state(now), ErrorState::GStreamerError);
+        assert_eq!(errors.state(later), ErrorState::NoStation);
+
+        errors.expire(later);
+        assert_eq!(errors.active(now).count(), 1);
+    }
+}
@@ -0,0 +1,327 @@
+use rradio_messages::{CdError, StationError};
+
+use crate::error_list::ErrorState;
+use crate::localisation::{text, MessageId};
+
+/// ErrorDescription is everything shown on the screen about an error reported by rradio
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct ErrorDescription {
+    pub state: ErrorState,
+    /// headline is short enough to fit on one line, eg "CD tray open"
+    pub headline: String,
+    /// explanation gives the details, usually as reported by rradio, & can be any length, so may need to scroll
+    pub explanation: String,
+    /// remedy says what the user can do about the error, eg "Close the CD tray", & fits on two lines
+    pub remedy: String,
+}
+
+impl ErrorDescription {
+    fn new(state: ErrorState, headline: String, explanation: String, remedy: MessageId) -> Self {
+        ErrorDescription {
+            state,
+            headline,
+            explanation,
+            remedy: text(remedy, &[]),
+        }
+    }
+}
+
+/// describe_station_error describes an error that stopped rradio playing a station
+pub fn describe_station_error(error: &StationError) -> ErrorDescription {
+    match error {
+        StationError::StationsDirectoryIoError { directory, err } => ErrorDescription::new(
+            ErrorState::NotKnown,
+            text(MessageId::StationFolderError, &[]),
+            format!("{}: {}", directory, err),
+            MessageId::RemedyCheckStationsFolder,
+        ),
+        StationError::StationNotFound { index, directory } => ErrorDescription::new(
+            ErrorState::NoStation,
+            text(MessageId::NoStation, &[index]),
+            text(MessageId::StationNotFoundExplanation, &[directory]),
+            MessageId::RemedyTryAnotherChannel,
+        ),
+        StationError::BadStationFile(message) => ErrorDescription::new(
+            ErrorState::ProgrammerError,
+            text(MessageId::BadStationFile, &[]),
+            message.to_string(),
+            MessageId::RemedyFixStationFile,
+        ),
+        StationError::CdError(cd_error) => describe_cd_error(cd_error),
+        StationError::MountError(mount_error) => ErrorDescription::new(
+            ErrorState::MountError,
+            text(MessageId::MountError, &[]),
+            mount_error.to_string(),
+            MessageId::RemedyCheckUsb,
+        ),
+        StationError::UPnPError(message) => ErrorDescription::new(
+            ErrorState::UPnPError,
+            text(MessageId::UPnPError, &[]),
+            message.to_string(),
+            MessageId::RemedyCheckNetwork,
+        ),
+    }
+}
+
+/// describe_cd_error describes an error reading the CD
+pub fn describe_cd_error(error: &CdError) -> ErrorDescription {
+    let cd_error = |headline: String, explanation: String, remedy| {
+        ErrorDescription::new(ErrorState::CdError, headline, explanation, remedy)
+    };
+    match error {
+        CdError::CdNotEnabled => cd_error(
+            text(MessageId::CdNotEnabledShort, &[]),
+            text(MessageId::CdNotEnabled, &[]),
+            MessageId::RemedyRecompileWithCd,
+        ),
+        CdError::FailedToOpenDevice { code, message } => cd_error(
+            text(MessageId::CdDriveError, &[]),
+            match code {
+                // the code is a Linux error number, eg 123 means there is no CD in the drive
+                Some(code) => format!("{} {}", std::io::Error::from_raw_os_error(*code), message),
+                None => message.to_string(),
+            },
+            MessageId::RemedyCheckCdDrive,
+        ),
+        CdError::IoCtlError { code, message } => cd_error(
+            text(MessageId::CdIoctlError, &[code]),
+            message.to_string(),
+            MessageId::RemedyEjectAndRetry,
+        ),
+        CdError::CdIsData1 => data_cd("1"),
+        CdError::CdIsData2 => data_cd("2"),
+        CdError::CdIsXA21 => data_cd("XA21"),
+        CdError::CdIsXA22 => data_cd("XA22"),
+        CdError::UnknownDiscStatus(status) => cd_error(
+            text(MessageId::CdStatusUnknown, &[]),
+            status.to_string(),
+            MessageId::RemedyEjectAndRetry,
+        ),
+        CdError::NoCd => cd_error(
+            text(MessageId::NoCd, &[]),
+            String::new(),
+            MessageId::RemedyInsertCd,
+        ),
+        CdError::CdTrayIsOpen => cd_error(
+            text(MessageId::CdTrayOpen, &[]),
+            String::new(),
+            MessageId::RemedyCloseCdTray,
+        ),
+        CdError::CdTrayIsNotReady => cd_error(
+            text(MessageId::CdTrayNotReady, &[]),
+            String::new(),
+            MessageId::RemedyWaitAndRetry,
+        ),
+        CdError::UnknownDriveStatus(status) => cd_error(
+            text(MessageId::CdDriveStatusUnknown, &[]),
+            status.to_string(),
+            MessageId::RemedyEjectAndRetry,
+        ),
+        CdError::NoCdInfo => cd_error(
+            text(MessageId::NoCdInfo, &[]),
+            String::new(),
+            MessageId::RemedyEjectAndRetry,
+        ),
+    }
+}
+
+/// data_cd describes all the kinds of data CD
+fn data_cd(data_type: &str) -> ErrorDescription {
+    ErrorDescription::new(
+        ErrorState::CdError,
+        text(MessageId::DataCdShort, &[&data_type]),
+        text(MessageId::DataCd, &[&data_type]),
+        MessageId::RemedyInsertAudioCd,
+    )
+}
+
+/// describe_error describes the latest error reported by rradio while playing, which is usually a gstreamer error
+pub fn describe_error(error: &rradio_messages::Error) -> ErrorDescription {
+    match error {
+        rradio_messages::Error::NoPlaylist => ErrorDescription::new(
+            ErrorState::GStreamerError,
+            text(MessageId::NoPlaylist, &[]),
+            String::new(),
+            MessageId::RemedyTryAnotherChannel,
+        ),
+        rradio_messages::Error::InvalidTrackIndex(index) => ErrorDescription::new(
+            ErrorState::GStreamerError,
+            text(MessageId::InvalidTrack, &[]),
+            index.to_string(),
+            MessageId::RemedyTryAnotherChannel,
+        ),
+        rradio_messages::Error::StationError(station_error) => {
+            describe_station_error(station_error)
+        }
+        rradio_messages::Error::TagError(message) => ErrorDescription::new(
+            ErrorState::GStreamerError,
+            text(MessageId::TagError, &[]),
+            message.to_string(),
+            MessageId::RemedyWaitOrTryAnotherChannel,
+        ),
+        rradio_messages::Error::PipelineError(message) => ErrorDescription::new(
+            ErrorState::GStreamerError,
+            text(MessageId::PlaybackError, &[]),
+            message.to_string(),
+            MessageId::RemedyWaitOrTryAnotherChannel,
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lcd::NUM_CHARACTERS_PER_LINE;
+    use rradio_messages::{MountError, StationIndex};
+
+    /// every_cd_error returns one of each CdError. The match has no wildcard, so it stops compiling
+    /// when rradio-messages adds a variant, as a reminder to add it to the list & to describe_cd_error.
+    fn every_cd_error() -> Vec<CdError> {
+        let every_cd_error = vec![
+            CdError::CdNotEnabled,
+            CdError::FailedToOpenDevice {
+                code: Some(123),
+                message: "/dev/cdrom".into(),
+            },
+            CdError::FailedToOpenDevice {
+                code: None,
+                message: "/dev/cdrom".into(),
+            },
+            CdError::IoCtlError {
+                code: -22,
+                message: "CDROM_DISC_STATUS".into(),
+            },
+            CdError::CdIsData1,
+            CdError::CdIsData2,
+            CdError::CdIsXA21,
+            CdError::CdIsXA22,
+            CdError::UnknownDiscStatus(42),
+            CdError::NoCd,
+            CdError::CdTrayIsOpen,
+            CdError::CdTrayIsNotReady,
+            CdError::UnknownDriveStatus(42),
+            CdError::NoCdInfo,
+        ];
+        for cd_error in &every_cd_error {
+            match cd_error {
+                CdError::CdNotEnabled
+                | CdError::FailedToOpenDevice { .. }
+                | CdError::IoCtlError { .. }
+                | CdError::CdIsData1
+                | CdError::CdIsData2
+                | CdError::CdIsXA21
+                | CdError::CdIsXA22
+                | CdError::UnknownDiscStatus(_)
+                | CdError::NoCd
+                | CdError::CdTrayIsOpen
+                | CdError::CdTrayIsNotReady
+                | CdError::UnknownDriveStatus(_)
+                | CdError::NoCdInfo => {}
+            }
+        }
+        every_cd_error
+    }
+
+    /// every_station_error returns one of each StationError, including every CdError
+    fn every_station_error() -> Vec<StationError> {
+        let mut every_station_error = vec![
+            StationError::StationsDirectoryIoError {
+                directory: "/home/pi/stations".into(),
+                err: "No such file or directory (os error 2)".into(),
+            },
+            StationError::StationNotFound {
+                index: StationIndex::new("42".to_string()),
+                directory: "/home/pi/stations".into(),
+            },
+            StationError::BadStationFile("00 Radio 4: missing URL".into()),
+            StationError::MountError(MountError::Other("/dev/sda1".into())),
+            StationError::UPnPError("server not found".into()),
+        ];
+        every_station_error.extend(every_cd_error().into_iter().map(StationError::CdError));
+        for station_error in &every_station_error {
+            match station_error {
+                StationError::StationsDirectoryIoError { .. }
+                | StationError::StationNotFound { .. }
+                | StationError::BadStationFile(_)
+                | StationError::CdError(_)
+                | StationError::MountError(_)
+                | StationError::UPnPError(_) => {}
+            }
+        }
+        every_station_error
+    }
+
+    /// every_error returns one of each rradio Error, including every StationError
+    fn every_error() -> Vec<rradio_messages::Error> {
+        let mut every_error = vec![
+            rradio_messages::Error::NoPlaylist,
+            rradio_messages::Error::InvalidTrackIndex(12),
+            rradio_messages::Error::TagError("bad tag".into()),
+            rradio_messages::Error::PipelineError("Internal data stream error".into()),
+        ];
+        every_error.extend(
+            every_station_error()
+                .into_iter()
+                .map(rradio_messages::Error::StationError),
+        );
+        for error in &every_error {
+            match error {
+                rradio_messages::Error::NoPlaylist
+                | rradio_messages::Error::InvalidTrackIndex(_)
+                | rradio_messages::Error::StationError(_)
+                | rradio_messages::Error::TagError(_)
+                | rradio_messages::Error::PipelineError(_) => {}
+            }
+        }
+        every_error
+    }
+
+    #[test]
+    fn every_error_is_described() {
+        for error in every_error() {
+            let description = describe_error(&error);
+            assert!(
+                !description.headline.is_empty(),
+                "{:?} has no headline",
+                error
+            );
+            assert!(
+                description.headline.chars().count() <= NUM_CHARACTERS_PER_LINE,
+                "the headline of {:?} is too long: \"{}\"",
+                error,
+                description.headline
+            );
+            assert!(
+                description.remedy.chars().count() <= NUM_CHARACTERS_PER_LINE * 2,
+                "the remedy of {:?} is too long: \"{}\"",
+                error,
+                description.remedy
+            );
+            assert_ne!(description.state, ErrorState::NoError, "{:?}", error);
+        }
+    }
+
+    #[test]
+    fn linux_error_codes_are_explained() {
+        let description = describe_cd_error(&CdError::FailedToOpenDevice {
+            code: Some(123),
+            message: "/dev/cdrom".into(),
+        });
+        assert_eq!(description.state, ErrorState::CdError);
+        assert_eq!(description.headline, "Cannot open CD drive");
+        assert!(description.explanation.ends_with("/dev/cdrom"));
+        assert!(!description.explanation.contains("CD missing")); // the meaning on Windows, which is of no use on a Pi
+    }
+
+    #[test]
+    fn station_not_found_says_which_station() {
+        let description = describe_station_error(&StationError::StationNotFound {
+            index: StationIndex::new("42".to_string()),
+            directory: "/home/pi/stations".into(),
+        });
+        assert_eq!(description.state, ErrorState::NoStation);
+        assert_eq!(description.headline, "No station 42");
+        assert_eq!(description.explanation, "Not in /home/pi/stations");
+        assert_eq!(description.remedy, "Try another channel");
+    }
+}
@@ -0,0 +1,169 @@
+use rradio_messages::Event;
+use serde::Deserialize;
+use std::time::Duration;
+use tokio::time::Instant;
+
+use crate::compositor::Layer;
+use crate::lcd::{LineNum, NUM_CHARACTERS_PER_LINE};
+use crate::localisation::{text, MessageId};
+
+/// EventKind is every kind of thing rradio can tell us. The version of rradio-messages we build against (0.38)
+/// has only the one variant of Event, PlayerStateChanged, so that is the only kind that comes from an Event.
+/// As well as that, rradio can send a message we cannot decode, eg because it is a newer version with events
+/// we do not know about, & it closes the connection when it shuts down.
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum EventKind {
+    PlayerStateChanged,
+    Undecodable,
+    ConnectionClosed,
+}
+
+impl EventKind {
+    /// of returns the kind of the event. There is deliberately no wildcard, so that when rradio-messages
+    /// adds a new event this stops compiling until it has been decided how the new event is shown.
+    pub fn of(event: &Event) -> EventKind {
+        match event {
+            Event::PlayerStateChanged(_) => EventKind::PlayerStateChanged,
+        }
+    }
+
+    fn message_id(self) -> MessageId {
+        match self {
+            EventKind::PlayerStateChanged => MessageId::EventPlayerStateChanged,
+            EventKind::Undecodable => MessageId::EventUndecodable,
+            EventKind::ConnectionClosed => MessageId::EventConnectionClosed,
+        }
+    }
+}
+
+/// Policy is what is done when an event arrives, as well as updating the player state if it is a player state change
+#[derive(PartialEq, Eq, Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Policy {
+    Overlay, // show a message on lines 3 & 4 for a while, & log the event
+    Log,     // only print the event
+    Ignore,
+}
+
+/// EventPolicy is the configuration of one kind of event, eg
+/// ```toml
+/// [events.undecodable]
+/// policy = "overlay"
+/// overlay_secs = 5
+/// ```
+#[derive(Debug, Clone, Deserialize)]
+pub struct EventPolicy {
+    pub policy: Policy,
+    /// how long the overlay is shown for, if the policy is overlay
+    #[serde(default = "default_overlay_secs")]
+    pub overlay_secs: u64,
+}
+
+fn default_overlay_secs() -> u64 {
+    5
+}
+
+impl EventPolicy {
+    fn new(policy: Policy) -> Self {
+        EventPolicy {
+            policy,
+            overlay_secs: default_overlay_secs(),
+        }
+    }
+}
+
+/// EventsConfig holds the policy for each kind of event
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct EventsConfig {
+    pub player_state_changed: EventPolicy,
+    pub undecodable: EventPolicy,
+    pub connection_closed: EventPolicy,
+}
+
+impl Default for EventsConfig {
+    fn default() -> Self {
+        EventsConfig {
+            player_state_changed: EventPolicy::new(Policy::Log), // the screen shows the new state anyway
+            undecodable: EventPolicy::new(Policy::Overlay),
+            connection_closed: EventPolicy::new(Policy::Overlay),
+        }
+    }
+}
+
+impl EventsConfig {
+    /// policy returns the policy for the kind of event
+    pub fn policy(&self, kind: EventKind) -> &EventPolicy {
+        match kind {
+            EventKind::PlayerStateChanged => &self.player_state_changed,
+            EventKind::Undecodable => &self.undecodable,
+            EventKind::ConnectionClosed => &self.connection_closed,
+        }
+    }
+
+    /// handle logs the event if the policy says so, & returns the overlay to show if there is one.
+    /// The details are only logged, as they are usually too long for the screen.
+    pub fn handle(&self, kind: EventKind, details: &str, now: Instant) -> Option<Overlay> {
+        let event_policy = self.policy(kind);
+        match event_policy.policy {
+            Policy::Overlay => {
+                println!("{:?} {}", kind, details);
+                Some(Overlay {
+                    text: text(kind.message_id(), &[]),
+                    until: now + Duration::from_secs(event_policy.overlay_secs),
+                })
+            }
+            Policy::Log => {
+                println!("{:?} {}", kind, details);
+                None
+            }
+            Policy::Ignore => None,
+        }
+    }
+}
+
+/// Overlay is a message about an event that is shown on top of whatever else is on the screen until it expires
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct Overlay {
+    pub text: String,
+    pub until: Instant,
+}
+
+impl Overlay {
+    /// layer returns the overlay on lines 3 & 4, which hides whatever is underneath it
+    pub fn layer(&self) -> Layer {
+        let mut layer = Layer::new();
+        layer.write_multiline(LineNum::Line3, NUM_CHARACTERS_PER_LINE * 2, &self.text);
+        layer
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn policies_can_be_configured() {
+        let config: EventsConfig = toml::from_str(
+            r#"
+            [undecodable]
+            policy = "ignore"
+
+            [connection_closed]
+            policy = "overlay"
+            overlay_secs = 10
+            "#,
+        )
+        .unwrap();
+        let now = Instant::now();
+        assert_eq!(
+            config.policy(EventKind::PlayerStateChanged).policy,
+            Policy::Log
+        ); // not in the file, so the default is kept
+        assert_eq!(config.handle(EventKind::Undecodable, "", now), None);
+
+        let overlay = config.handle(EventKind::ConnectionClosed, "", now).unwrap();
+        assert_eq!(overlay.text, "rradio has stopped");
+        assert_eq!(overlay.until, now + Duration::from_secs(10));
+    }
+}
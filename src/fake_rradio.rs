@@ -0,0 +1,404 @@
+use rradio_messages::{
+    CdError, Command, CurrentStation, Event, LatestError, PingTarget, PingTimes, PipelineState,
+    PlayerStateDiff, StationError, StationIndex, StationType, Track, TrackTags,
+};
+use serde::Deserialize;
+use std::time::Duration;
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt};
+
+/// Header is the header the fake rradio sends when a connection is made. As well as the correct header,
+/// it can send each of the bad headers that the screen driver reports in a different way.
+#[derive(PartialEq, Eq, Debug, Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Header {
+    #[default]
+    Good,
+    NotUtf8, // eg a binary protocol
+    #[serde(rename = "not_rradio")]
+    NotRRadio, // eg a web server
+    WrongVersion, // rradio, but built with a different version of rradio-messages
+}
+
+impl Header {
+    /// bytes returns the header to send, which is always the same length as the correct header,
+    /// so that the screen driver reads all of it before deciding it is wrong
+    pub fn bytes(self) -> Vec<u8> {
+        let good_header = rradio_messages::API_VERSION_HEADER;
+        match self {
+            Header::Good => good_header.as_bytes().to_vec(),
+            Header::NotUtf8 => vec![0xff; good_header.len()],
+            Header::NotRRadio => format!(
+                "{:width$}\n",
+                "HTTP/1.1 200 OK",
+                width = good_header.len() - 1
+            )
+            .into_bytes(),
+            Header::WrongVersion => good_header
+                .chars()
+                .map(|one_char| match one_char {
+                    '9' => '0',
+                    '0'..='8' => '9', // change every digit so that the version must be different
+                    _ => one_char,
+                })
+                .collect::<String>()
+                .into_bytes(),
+        }
+    }
+}
+
+/// Scenario is what the fake rradio sends to each connection, read from a TOML file, eg
+/// ```toml
+/// header = "good"
+///
+/// [[steps]]
+/// station = { index = "07", title = "Jazz FM" }
+/// pipeline_state = "Playing"
+/// volume = 70
+///
+/// [[steps]]
+/// delay_ms = 5000
+/// cd_error = "CdTrayIsOpen"
+/// ```
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Scenario {
+    pub header: Header,
+    pub steps: Vec<Step>,
+}
+
+/// StationStep is a station that rradio has started playing
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct StationStep {
+    pub index: Option<String>,
+    pub title: Option<String>,
+    #[serde(default = "default_source_type")]
+    pub source_type: StationType,
+    /// the number of tracks, which only matters for CDs & USB sticks
+    #[serde(default)]
+    pub tracks: usize,
+}
+
+fn default_source_type() -> StationType {
+    StationType::UrlList
+}
+
+/// TagsStep is the tags of the track being played
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TagsStep {
+    pub title: Option<String>,
+    pub artist: Option<String>,
+    pub album: Option<String>,
+    pub organisation: Option<String>,
+    pub genre: Option<String>,
+}
+
+/// PingStep is the latest ping times. If there is no remote time, only the gateway has been pinged.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PingStep {
+    pub gateway_ms: f64,
+    pub remote_ms: Option<f64>,
+}
+
+/// Step is one message from the fake rradio. Most fields are optional, & are only sent if they are given,
+/// in the same way that rradio only sends what has changed.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Step {
+    /// how long to wait before sending the step
+    pub delay_ms: u64,
+    pub pipeline_state: Option<PipelineState>,
+    pub station: Option<StationStep>,
+    /// the channel asked for does not exist
+    pub station_not_found: Option<String>,
+    /// rradio stopped playing the station, eg when the CD is ejected
+    pub no_station: bool,
+    pub cd_error: Option<CdError>,
+    /// any other error from trying to play a station, eg `station_error = { BadStationFile = "missing URL" }`
+    pub station_error: Option<StationError>,
+    pub tags: Option<TagsStep>,
+    pub clear_tags: bool,
+    pub volume: Option<i32>,
+    pub is_muted: Option<bool>,
+    pub buffering: Option<u8>,
+    pub track_index: Option<usize>,
+    pub track_duration_secs: Option<u64>,
+    pub track_position_secs: Option<u64>,
+    pub ping: Option<PingStep>,
+    /// a gstreamer error
+    pub error: Option<String>,
+    /// rradio has recovered from the gstreamer error
+    pub clear_error: bool,
+    /// send bytes that are not a valid event, eg as if rradio were a newer version
+    pub send_garbage: bool,
+    /// close the connection after this step, as rradio does when it shuts down
+    pub close: bool,
+}
+
+impl Step {
+    /// send sends the step straight away, ignoring the delay, & closes the connection if the step says so
+    pub async fn send<S: AsyncWrite + Unpin>(&self, stream: &mut S) -> std::io::Result<()> {
+        if let Some(diff) = self.diff() {
+            stream
+                .write_all(&encode(&Event::PlayerStateChanged(diff)))
+                .await?;
+        }
+        if self.send_garbage {
+            stream.write_all(&[0xff, 0xfe, 0xfd, 0x00]).await?;
+        }
+        stream.flush().await?;
+        if self.close {
+            stream.shutdown().await?;
+        }
+        Ok(())
+    }
+
+    /// diff returns what the step changes, or None if the step does not change anything
+    pub fn diff(&self) -> Option<PlayerStateDiff> {
+        let station_error = |error: StationError| CurrentStation::FailedToPlayStation { error };
+        let current_station = if let Some(station) = &self.station {
+            Some(CurrentStation::PlayingStation {
+                index: station.index.clone().map(StationIndex::new),
+                source_type: station.source_type,
+                title: station.title.as_deref().map(Into::into),
+                tracks: Some(
+                    (1..=station.tracks)
+                        .map(|track_number| Track {
+                            title: Some(format!("Track {}", track_number).into()),
+                            album: None,
+                            artist: None,
+                            url: format!("cdda://{}", track_number).into(),
+                            is_notification: false,
+                        })
+                        .collect::<Vec<_>>()
+                        .into(),
+                ),
+            })
+        } else if let Some(index) = &self.station_not_found {
+            Some(station_error(StationError::StationNotFound {
+                index: StationIndex::new(index.clone()),
+                directory: "/home/pi/stations".into(),
+            }))
+        } else if let Some(cd_error) = &self.cd_error {
+            Some(station_error(StationError::CdError(cd_error.clone())))
+        } else if let Some(error) = &self.station_error {
+            Some(station_error(error.clone()))
+        } else if self.no_station {
+            Some(CurrentStation::NoStation)
+        } else {
+            None
+        };
+        let current_track_tags = if let Some(tags) = &self.tags {
+            let tag = |tag: &Option<String>| tag.as_deref().map(Into::into);
+            Some(Some(TrackTags {
+                title: tag(&tags.title),
+                artist: tag(&tags.artist),
+                album: tag(&tags.album),
+                organisation: tag(&tags.organisation),
+                genre: tag(&tags.genre),
+                ..TrackTags::default()
+            }))
+        } else if self.clear_tags {
+            Some(None)
+        } else {
+            None
+        };
+        let latest_error = if let Some(error) = &self.error {
+            Some(Some(LatestError {
+                timestamp: 0,
+                error: rradio_messages::Error::PipelineError(error.as_str().into()),
+            }))
+        } else if self.clear_error {
+            Some(None)
+        } else {
+            None
+        };
+        let milliseconds =
+            |milliseconds: f64| Duration::from_micros((milliseconds * 1000.0) as u64);
+        let diff = PlayerStateDiff {
+            pipeline_state: self.pipeline_state,
+            current_station,
+            current_track_index: self.track_index,
+            current_track_tags,
+            is_muted: self.is_muted,
+            volume: self.volume,
+            buffering: self.buffering,
+            track_duration: self
+                .track_duration_secs
+                .map(|secs| Some(Duration::from_secs(secs))),
+            track_position: self
+                .track_position_secs
+                .map(|secs| Some(Duration::from_secs(secs))),
+            ping_times: self.ping.as_ref().map(|ping| match ping.remote_ms {
+                Some(remote_ms) => PingTimes::GatewayAndRemote {
+                    gateway_ping: milliseconds(ping.gateway_ms),
+                    remote_ping: Ok(milliseconds(remote_ms)),
+                    latest: PingTarget::Remote,
+                },
+                None => PingTimes::Gateway(Ok(milliseconds(ping.gateway_ms))),
+            }),
+            latest_error,
+            ..PlayerStateDiff::default()
+        };
+        let changes_something = diff.pipeline_state.is_some()
+            || diff.current_station.is_some()
+            || diff.current_track_index.is_some()
+            || diff.current_track_tags.is_some()
+            || diff.is_muted.is_some()
+            || diff.volume.is_some()
+            || diff.buffering.is_some()
+            || diff.track_duration.is_some()
+            || diff.track_position.is_some()
+            || diff.ping_times.is_some()
+            || diff.latest_error.is_some();
+        if changes_something {
+            Some(diff)
+        } else {
+            None
+        }
+    }
+}
+
+/// encode encodes the event in the same way as rradio
+pub fn encode(event: &Event) -> Vec<u8> {
+    postcard::to_stdvec_cobs(event).expect("events can always be encoded")
+}
+
+/// read_command reads the next command sent by the screen driver, or returns None if the screen driver has closed the connection
+pub async fn read_command<S: AsyncBufRead + Unpin>(
+    stream: &mut S,
+) -> std::io::Result<Option<Command>> {
+    let mut encoded = Vec::new();
+    if stream.read_until(0, &mut encoded).await? == 0 {
+        return Ok(None);
+    }
+    postcard::from_bytes_cobs(&mut encoded)
+        .map(Some)
+        .map_err(|error| std::io::Error::new(std::io::ErrorKind::InvalidData, error))
+}
+
+impl Scenario {
+    /// load reads the scenario from a TOML file
+    pub fn load(path: &str) -> Result<Scenario, anyhow::Error> {
+        let scenario_as_string = std::fs::read_to_string(path)
+            .map_err(|error| anyhow::anyhow!("Could not read the scenario {}: {}", path, error))?;
+        toml::from_str(&scenario_as_string)
+            .map_err(|error| anyhow::anyhow!("The scenario {} is not valid: {}", path, error))
+    }
+
+    /// play sends the scenario to a connection. If the scenario does not close the connection, it then records
+    /// the commands sent by the screen driver until the screen driver closes the connection, & returns them.
+    pub async fn play<S: AsyncRead + AsyncWrite + Unpin>(
+        &self,
+        stream: &mut S,
+    ) -> std::io::Result<Vec<Command>> {
+        let mut stream = tokio::io::BufReader::new(stream);
+        stream.write_all(&self.header.bytes()).await?;
+        if self.header != Header::Good {
+            return Ok(Vec::new()); // the screen driver gives up straight away, so there is no point sending anything else
+        }
+        for step in &self.steps {
+            tokio::time::sleep(Duration::from_millis(step.delay_ms)).await;
+            step.send(&mut stream).await?;
+            if step.close {
+                return Ok(Vec::new());
+            }
+        }
+        let mut commands = Vec::new();
+        while let Some(command) = read_command(&mut stream).await? {
+            println!("received {:?}", command);
+            commands.push(command);
+        }
+        Ok(commands)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures_util::StreamExt;
+
+    #[tokio::test]
+    async fn the_screen_driver_can_decode_the_scenario() {
+        let scenario: Scenario = toml::from_str(
+            r#"
+            [[steps]]
+            station = { index = "07", title = "Jazz FM" }
+            volume = 70
+
+            [[steps]]
+            cd_error = "CdTrayIsOpen"
+            send_garbage = true
+            close = true
+            "#,
+        )
+        .unwrap();
+        let (mut rradio, screen_driver) = tokio::io::duplex(1024);
+        let (played, events) = tokio::join!(scenario.play(&mut rradio), async {
+            rradio_messages::Event::decode_from_stream(tokio::io::BufReader::new(screen_driver))
+                .await
+                .unwrap()
+                .collect::<Vec<_>>()
+                .await
+        });
+        played.unwrap();
+
+        assert_eq!(events.len(), 3);
+        match &events[0] {
+            Ok(Event::PlayerStateChanged(diff)) => {
+                assert_eq!(diff.volume, Some(70));
+                assert!(matches!(
+                    diff.current_station,
+                    Some(CurrentStation::PlayingStation { .. })
+                ));
+            }
+            other => panic!("expected a station, got {:?}", other),
+        }
+        match &events[1] {
+            Ok(Event::PlayerStateChanged(diff)) => assert!(matches!(
+                diff.current_station,
+                Some(CurrentStation::FailedToPlayStation {
+                    error: StationError::CdError(CdError::CdTrayIsOpen)
+                })
+            )),
+            other => panic!("expected a CD error, got {:?}", other),
+        }
+        assert!(events[2].is_err()); // the garbage
+    }
+
+    #[test]
+    fn every_example_scenario_is_valid() {
+        let scenarios = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("scenarios");
+        for entry in std::fs::read_dir(scenarios).unwrap() {
+            let path = entry.unwrap().path();
+            if let Err(error) = Scenario::load(&path.to_string_lossy()) {
+                panic!("{}", error);
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn bad_headers_are_rejected() {
+        for header in [Header::NotUtf8, Header::NotRRadio, Header::WrongVersion].iter() {
+            let (mut rradio, screen_driver) = tokio::io::duplex(1024);
+            rradio.write_all(&header.bytes()).await.unwrap();
+            let actual = match rradio_messages::Event::decode_from_stream(
+                tokio::io::BufReader::new(screen_driver),
+            )
+            .await
+            {
+                Err(rradio_messages::BadRRadioHeader::HeaderMismatch { actual, .. }) => actual,
+                _ => panic!("the {:?} header was accepted", header),
+            };
+            let actual = std::str::from_utf8(&actual);
+            match header {
+                Header::NotUtf8 => assert!(actual.is_err()),
+                Header::NotRRadio => assert!(!actual.unwrap().starts_with("rradio-messages_")),
+                Header::WrongVersion => assert!(actual.unwrap().starts_with("rradio-messages_")),
+                Header::Good => unreachable!(),
+            }
+        }
+    }
+}
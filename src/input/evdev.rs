@@ -0,0 +1,398 @@
+//! IR remotes & USB keypads, read through the Linux input subsystem (evdev), eg /dev/input/event0.
+//! The kernel turns the key presses into input events, whatever the device, so the only thing that differs between
+//! a remote & a keypad is which keys it has. An IR remote needs its keymap loaded with ir-keytable first.
+//! The device should be given by one of its names in /dev/input/by-id or /dev/input/by-path, as the numbers
+//! of the event files can change when the Pi is rebooted.
+
+use serde::Deserialize;
+use std::collections::HashMap;
+use tokio::sync::mpsc;
+
+use super::{Action, ActionSender};
+
+/// EvdevConfig is the configuration of the remotes & keypads, eg
+/// ```toml
+/// [evdev]
+/// devices = [ "/dev/input/by-id/usb-Keypad-event-kbd" ]
+///
+/// [evdev.keymap]
+/// KEY_KPPLUS = "volume_up"
+/// KEY_KPMINUS = "volume_down"
+/// KEY_KPENTER = "next_page"
+/// KEY_KP1 = { digit = 1 }
+/// ```
+/// A keymap in the config file replaces the whole of the default keymap, so it must include every key that is wanted.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct EvdevConfig {
+    /// the event files of the remotes & keypads
+    pub devices: Vec<String>,
+    /// the action for each key, by the name given to the key in linux/input-event-codes.h
+    pub keymap: HashMap<String, Action>,
+}
+
+impl Default for EvdevConfig {
+    fn default() -> Self {
+        let digits = |names: [&str; 10]| {
+            names
+                .iter()
+                .zip(0..)
+                .map(|(name, digit)| (name.to_string(), Action::Digit(digit)))
+                .collect::<Vec<_>>()
+        };
+        let mut keymap: HashMap<String, Action> = [
+            ("KEY_VOLUMEUP", Action::VolumeUp),
+            ("KEY_VOLUMEDOWN", Action::VolumeDown),
+            ("KEY_KPPLUS", Action::VolumeUp),
+            ("KEY_KPMINUS", Action::VolumeDown),
+            ("KEY_MUTE", Action::Mute),
+            ("KEY_CHANNELUP", Action::StationUp),
+            ("KEY_CHANNELDOWN", Action::StationDown),
+            ("KEY_NEXT", Action::NextPage),
+            ("KEY_INFO", Action::NextPage),
+            ("KEY_MENU", Action::Menu),
+        ]
+        .iter()
+        .map(|(name, action)| (name.to_string(), *action))
+        .collect();
+        // remotes send the numeric keys, USB keypads the keypad keys & keyboards the number keys
+        keymap.extend(digits([
+            "KEY_NUMERIC_0",
+            "KEY_NUMERIC_1",
+            "KEY_NUMERIC_2",
+            "KEY_NUMERIC_3",
+            "KEY_NUMERIC_4",
+            "KEY_NUMERIC_5",
+            "KEY_NUMERIC_6",
+            "KEY_NUMERIC_7",
+            "KEY_NUMERIC_8",
+            "KEY_NUMERIC_9",
+        ]));
+        keymap.extend(digits([
+            "KEY_KP0", "KEY_KP1", "KEY_KP2", "KEY_KP3", "KEY_KP4", "KEY_KP5", "KEY_KP6", "KEY_KP7",
+            "KEY_KP8", "KEY_KP9",
+        ]));
+        keymap.extend(digits([
+            "KEY_0", "KEY_1", "KEY_2", "KEY_3", "KEY_4", "KEY_5", "KEY_6", "KEY_7", "KEY_8",
+            "KEY_9",
+        ]));
+        EvdevConfig {
+            devices: Vec::new(),
+            keymap,
+        }
+    }
+}
+
+/// KEY_CODES are the names & codes of the keys, from linux/input-event-codes.h, that remotes & keypads are likely to have
+const KEY_CODES: &[(&str, u16)] = &[
+    ("KEY_ESC", 1),
+    ("KEY_1", 2),
+    ("KEY_2", 3),
+    ("KEY_3", 4),
+    ("KEY_4", 5),
+    ("KEY_5", 6),
+    ("KEY_6", 7),
+    ("KEY_7", 8),
+    ("KEY_8", 9),
+    ("KEY_9", 10),
+    ("KEY_0", 11),
+    ("KEY_BACKSPACE", 14),
+    ("KEY_TAB", 15),
+    ("KEY_ENTER", 28),
+    ("KEY_KPASTERISK", 55),
+    ("KEY_SPACE", 57),
+    ("KEY_NUMLOCK", 69),
+    ("KEY_KP7", 71),
+    ("KEY_KP8", 72),
+    ("KEY_KP9", 73),
+    ("KEY_KPMINUS", 74),
+    ("KEY_KP4", 75),
+    ("KEY_KP5", 76),
+    ("KEY_KP6", 77),
+    ("KEY_KPPLUS", 78),
+    ("KEY_KP1", 79),
+    ("KEY_KP2", 80),
+    ("KEY_KP3", 81),
+    ("KEY_KP0", 82),
+    ("KEY_KPDOT", 83),
+    ("KEY_KPENTER", 96),
+    ("KEY_KPSLASH", 98),
+    ("KEY_UP", 103),
+    ("KEY_PAGEUP", 104),
+    ("KEY_LEFT", 105),
+    ("KEY_RIGHT", 106),
+    ("KEY_DOWN", 108),
+    ("KEY_PAGEDOWN", 109),
+    ("KEY_MUTE", 113),
+    ("KEY_VOLUMEDOWN", 114),
+    ("KEY_VOLUMEUP", 115),
+    ("KEY_POWER", 116),
+    ("KEY_MENU", 139),
+    ("KEY_BACK", 158),
+    ("KEY_EJECTCD", 161),
+    ("KEY_NEXTSONG", 163),
+    ("KEY_PLAYPAUSE", 164),
+    ("KEY_PREVIOUSSONG", 165),
+    ("KEY_STOPCD", 166),
+    ("KEY_OK", 352),
+    ("KEY_SELECT", 353),
+    ("KEY_INFO", 358),
+    ("KEY_CHANNELUP", 402),
+    ("KEY_CHANNELDOWN", 403),
+    ("KEY_NEXT", 407),
+    ("KEY_PREVIOUS", 412),
+    ("KEY_NUMERIC_0", 0x200),
+    ("KEY_NUMERIC_1", 0x201),
+    ("KEY_NUMERIC_2", 0x202),
+    ("KEY_NUMERIC_3", 0x203),
+    ("KEY_NUMERIC_4", 0x204),
+    ("KEY_NUMERIC_5", 0x205),
+    ("KEY_NUMERIC_6", 0x206),
+    ("KEY_NUMERIC_7", 0x207),
+    ("KEY_NUMERIC_8", 0x208),
+    ("KEY_NUMERIC_9", 0x209),
+];
+
+/// the type of the events for keys & buttons; the others, eg EV_SYN & EV_MSC, are of no interest
+const EV_KEY: u16 = 1;
+
+/// the values of a key event
+const KEY_PRESSED: i32 = 1;
+const KEY_REPEATED: i32 = 2; // sent while the key is held down
+
+/// InputEvent is the part of a struct input_event from the kernel that matters, ie without the time it happened
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub struct InputEvent {
+    pub event_type: u16,
+    pub code: u16,
+    pub value: i32,
+}
+
+/// EventDecoder splits the bytes read from an event file into input events. Each event is a struct timeval,
+/// which is two longs, followed by the type, code & value. A read can end part way through an event,
+/// so what is left over is kept until the rest arrives.
+#[derive(Debug)]
+pub struct EventDecoder {
+    time_size: usize,
+    unused_bytes: Vec<u8>,
+}
+
+impl EventDecoder {
+    /// new returns a decoder for the events of this computer, where a long is the same size as a pointer
+    pub fn new() -> Self {
+        Self::with_time_size(2 * std::mem::size_of::<usize>())
+    }
+
+    /// with_time_size returns a decoder for events whose time takes the specified number of bytes,
+    /// ie 16 on a 64 bit computer & 8 on a 32 bit one
+    pub fn with_time_size(time_size: usize) -> Self {
+        EventDecoder {
+            time_size,
+            unused_bytes: Vec::new(),
+        }
+    }
+
+    /// event_size is the number of bytes in each event
+    pub fn event_size(&self) -> usize {
+        self.time_size + 8
+    }
+
+    /// decode returns the events completed by the bytes
+    pub fn decode(&mut self, bytes: &[u8]) -> Vec<InputEvent> {
+        self.unused_bytes.extend_from_slice(bytes);
+        let event_size = self.event_size();
+        let events = self
+            .unused_bytes
+            .chunks_exact(event_size)
+            .map(|event| {
+                let fields = &event[self.time_size..];
+                InputEvent {
+                    event_type: u16::from_ne_bytes([fields[0], fields[1]]),
+                    code: u16::from_ne_bytes([fields[2], fields[3]]),
+                    value: i32::from_ne_bytes([fields[4], fields[5], fields[6], fields[7]]),
+                }
+            })
+            .collect::<Vec<_>>();
+        self.unused_bytes.drain(..events.len() * event_size);
+        events
+    }
+}
+
+impl Default for EventDecoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// EvdevInput turns the key presses into actions, using the keymap
+#[derive(Debug)]
+pub struct EvdevInput {
+    keymap: HashMap<u16, (&'static str, Action)>, // the name & action of each key, by its code
+}
+
+impl EvdevInput {
+    /// new returns the input for the keymap in the config. Keys whose names are not known are reported & ignored.
+    pub fn new(config: &EvdevConfig) -> Self {
+        let mut keymap = HashMap::new();
+        for (name, action) in &config.keymap {
+            match KEY_CODES.iter().find(|(key_name, _code)| key_name == name) {
+                Some((key_name, code)) => {
+                    keymap.insert(*code, (*key_name, *action));
+                }
+                None => println!(
+                    "{name} is not a key that is known, so it cannot be used for {action:?}"
+                ),
+            }
+        }
+        EvdevInput { keymap }
+    }
+
+    /// handle returns the action for the input event, & the name of the key to echo on the screen, if there is one.
+    /// Holding a key down repeats its action only for the volume, as repeating a station digit or the page is not wanted.
+    pub fn handle(&self, event: InputEvent) -> Option<(Action, String)> {
+        if event.event_type != EV_KEY {
+            return None;
+        }
+        let (name, action) = match self.keymap.get(&event.code) {
+            Some(&(name, action)) => (name, action),
+            None => {
+                if event.value == KEY_PRESSED {
+                    println!("Key code {} is not in the keymap", event.code);
+                }
+                return None;
+            }
+        };
+        let wanted = match event.value {
+            KEY_PRESSED => true,
+            KEY_REPEATED => matches!(action, Action::VolumeUp | Action::VolumeDown),
+            _ => false, // released
+        };
+        if wanted {
+            Some((action, name.trim_start_matches("KEY_").to_string()))
+        } else {
+            None
+        }
+    }
+}
+
+/// watch_devices reads the input events from each device in a thread of its own, as reading an event file blocks.
+/// If a device cannot be opened, the error is returned, so that the problem is reported.
+pub fn watch_devices(config: &EvdevConfig) -> anyhow::Result<mpsc::UnboundedReceiver<InputEvent>> {
+    use anyhow::Context;
+    use std::io::Read;
+
+    let (sender, receiver) = mpsc::unbounded_channel();
+    for device in &config.devices {
+        let mut file =
+            std::fs::File::open(device).with_context(|| format!("Failed to open {device}"))?;
+        let device = device.clone();
+        let sender = sender.clone();
+        std::thread::spawn(move || {
+            let mut decoder = EventDecoder::new();
+            let mut buffer = vec![0; 64 * decoder.event_size()];
+            loop {
+                let byte_count = match file.read(&mut buffer) {
+                    Ok(0) => {
+                        println!("{device} has been unplugged");
+                        return;
+                    }
+                    Ok(byte_count) => byte_count,
+                    Err(error) => {
+                        println!("Failed to read {device}, so no longer watching it: {error}");
+                        return;
+                    }
+                };
+                for event in decoder.decode(&buffer[..byte_count]) {
+                    if sender.send(event).is_err() {
+                        return; // nothing is interested in the device any more
+                    }
+                }
+            }
+        });
+    }
+    Ok(receiver)
+}
+
+/// run turns the input events into actions & sends them to the driver, with the name of the key, until there are no more input events
+pub async fn run(
+    input: EvdevInput,
+    mut events: mpsc::UnboundedReceiver<InputEvent>,
+    actions: ActionSender,
+) {
+    while let Some(event) = events.recv().await {
+        if let Some((action, key_name)) = input.handle(event) {
+            actions.send_echoed(action, key_name);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// a USB keypad, recorded on a 64 bit Pi, with Num Lock, 4, 2 & + held down long enough to repeat twice
+    const USB_KEYPAD: &[u8] = include_bytes!("testdata/usb_keypad.evdev");
+
+    fn actions(input: &EvdevInput, events: Vec<InputEvent>) -> Vec<Action> {
+        events
+            .into_iter()
+            .filter_map(|event| input.handle(event))
+            .map(|(action, _key_name)| action)
+            .collect()
+    }
+
+    #[test]
+    fn a_recorded_keypad_is_decoded() {
+        let input = EvdevInput::new(&EvdevConfig::default());
+        let events = EventDecoder::with_time_size(16).decode(USB_KEYPAD);
+        assert_eq!(
+            actions(&input, events),
+            vec![
+                Action::Digit(4),
+                Action::Digit(2),
+                Action::VolumeUp,
+                Action::VolumeUp,
+                Action::VolumeUp
+            ]
+        );
+    }
+
+    #[test]
+    fn events_split_across_reads_are_decoded() {
+        let mut decoder = EventDecoder::with_time_size(16);
+        let (first_part, rest) = USB_KEYPAD.split_at(30); // part way through the second event
+        let mut events = decoder.decode(first_part);
+        assert_eq!(events.len(), 1);
+        events.extend(decoder.decode(rest));
+        assert_eq!(events, EventDecoder::with_time_size(16).decode(USB_KEYPAD));
+    }
+
+    #[test]
+    fn the_keymap_can_be_configured() {
+        let config: EvdevConfig = toml::from_str(
+            r#"
+            devices = [ "/dev/input/event0" ]
+
+            [keymap]
+            KEY_OK = "next_page"
+            KEY_KP1 = { digit = 1 }
+            "#,
+        )
+        .unwrap();
+        let input = EvdevInput::new(&config);
+        let press = |code| InputEvent {
+            event_type: EV_KEY,
+            code,
+            value: KEY_PRESSED,
+        };
+        assert_eq!(
+            input.handle(press(352)),
+            Some((Action::NextPage, "OK".to_string()))
+        );
+        assert_eq!(
+            input.handle(press(79)),
+            Some((Action::Digit(1), "KP1".to_string()))
+        );
+        assert_eq!(input.handle(press(115)), None); // the default keymap has been replaced
+    }
+}
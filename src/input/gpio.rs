@@ -0,0 +1,663 @@
+//! Buttons & rotary encoders wired to the GPIO lines of the Pi that drives the screen.
+//! The lines are expected to be pulled up, either by resistors or by the Pi's own pull-ups set in config.txt, eg
+//!     gpio=5,6,13=ip,pu
+//! so that a button pulls its line low when it is pressed.
+
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tokio::time::Instant;
+
+use super::{Action, ActionSender};
+
+/// GpioConfig is the configuration of the controls on the GPIO lines, eg
+/// ```toml
+/// [gpio]
+/// long_press_ms = 1000
+///
+/// [[gpio.buttons]]
+/// line = 13
+/// short = "mute"
+/// long = "menu"
+///
+/// [[gpio.encoders]]
+/// line_a = 5
+/// line_b = 6
+/// clockwise = "volume_up"
+/// anticlockwise = "volume_down"
+/// ```
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct GpioConfig {
+    /// the GPIO chip the lines are on
+    pub chip: String,
+    /// how long a button's line has to stay the same after it changes before it is allowed to change again
+    pub debounce_ms: u64,
+    /// how long a button has to be held down for a long press
+    pub long_press_ms: u64,
+    /// how soon a button has to be pressed again for a double press
+    pub double_press_ms: u64,
+    pub buttons: Vec<ButtonConfig>,
+    pub encoders: Vec<EncoderConfig>,
+}
+
+impl Default for GpioConfig {
+    fn default() -> Self {
+        GpioConfig {
+            chip: "/dev/gpiochip0".to_string(),
+            debounce_ms: 20,
+            long_press_ms: 800,
+            double_press_ms: 300,
+            buttons: Vec::new(),
+            encoders: Vec::new(),
+        }
+    }
+}
+
+impl GpioConfig {
+    /// lines returns the offsets of all the lines used by the controls
+    pub fn lines(&self) -> Vec<u32> {
+        self.buttons
+            .iter()
+            .map(|button| button.line)
+            .chain(
+                self.encoders
+                    .iter()
+                    .flat_map(|encoder| vec![encoder.line_a, encoder.line_b]),
+            )
+            .collect()
+    }
+}
+
+/// ButtonConfig is a push button & the actions for each kind of press. A long press does the same as a short press
+/// if it has no action of its own. If there is no action for a double press, a short press is done as soon as the button
+/// is released, rather than after waiting to see if it is pressed again.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ButtonConfig {
+    pub line: u32,
+    pub short: Option<Action>,
+    pub long: Option<Action>,
+    pub double: Option<Action>,
+    /// true if the line is low while the button is pressed, which is usual when the line is pulled up
+    #[serde(default = "default_true")]
+    pub active_low: bool,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+/// EncoderConfig is a quadrature rotary encoder, such as a volume knob, & the actions for each direction
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct EncoderConfig {
+    pub line_a: u32,
+    pub line_b: u32,
+    pub clockwise: Option<Action>,
+    pub anticlockwise: Option<Action>,
+    /// how many changes of the lines there are between one click of the knob & the next. Most encoders have 4.
+    #[serde(default = "default_steps_per_detent")]
+    pub steps_per_detent: u8,
+}
+
+fn default_steps_per_detent() -> u8 {
+    4
+}
+
+/// LineEvent is a change of the level of a GPIO line, ie an edge
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub struct LineEvent {
+    pub line: u32,
+    pub high: bool,
+    pub time: Instant,
+}
+
+/// Press is the kind of press of a button
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum Press {
+    Short,
+    Long,
+    Double,
+}
+
+/// ButtonState is what a button is doing, as far as working out the kind of press is concerned
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+enum ButtonState {
+    Released,
+    Pressed { since: Instant, second_press: bool },
+    WaitingForSecondPress { released_at: Instant },
+    LongPressDone, // the long press has been reported, but the button has not yet been released
+}
+
+/// ButtonDecoder works out the kind of each press of a button from when the button is pressed & released
+#[derive(Debug)]
+pub struct ButtonDecoder {
+    state: ButtonState,
+    long_press_time: Duration,
+    double_press_time: Option<Duration>, // None if double presses are not wanted
+}
+
+impl ButtonDecoder {
+    pub fn new(long_press_time: Duration, double_press_time: Option<Duration>) -> Self {
+        ButtonDecoder {
+            state: ButtonState::Released,
+            long_press_time,
+            double_press_time,
+        }
+    }
+
+    /// edge is called when the button is pressed or released, & returns the press if it is complete
+    pub fn edge(&mut self, pressed: bool, now: Instant) -> Option<Press> {
+        match (self.state, pressed) {
+            (ButtonState::Released, true) => {
+                self.state = ButtonState::Pressed {
+                    since: now,
+                    second_press: false,
+                };
+                None
+            }
+            (ButtonState::WaitingForSecondPress { .. }, true) => {
+                self.state = ButtonState::Pressed {
+                    since: now,
+                    second_press: true,
+                };
+                None
+            }
+            (
+                ButtonState::Pressed {
+                    second_press: true, ..
+                },
+                false,
+            ) => {
+                self.state = ButtonState::Released;
+                Some(Press::Double)
+            }
+            (
+                ButtonState::Pressed {
+                    second_press: false,
+                    ..
+                },
+                false,
+            ) => match self.double_press_time {
+                Some(_) => {
+                    self.state = ButtonState::WaitingForSecondPress { released_at: now };
+                    None
+                }
+                None => {
+                    self.state = ButtonState::Released;
+                    Some(Press::Short)
+                }
+            },
+            (ButtonState::LongPressDone, false) => {
+                self.state = ButtonState::Released;
+                None
+            }
+            _ => None, // the button is already in the state the edge puts it in
+        }
+    }
+
+    /// deadline returns when timeout needs to be called next, if it does
+    pub fn deadline(&self) -> Option<Instant> {
+        match self.state {
+            ButtonState::Pressed {
+                since,
+                second_press: false,
+            } => Some(since + self.long_press_time),
+            ButtonState::WaitingForSecondPress { released_at } => self
+                .double_press_time
+                .map(|double_press_time| released_at + double_press_time),
+            _ => None,
+        }
+    }
+
+    /// timeout is called when the deadline has passed, & returns the press if it is now complete
+    pub fn timeout(&mut self, now: Instant) -> Option<Press> {
+        if self.deadline().map_or(true, |deadline| now < deadline) {
+            return None;
+        }
+        match self.state {
+            ButtonState::Pressed { .. } => {
+                self.state = ButtonState::LongPressDone; // it is reported while the button is still held, so that it is obvious when to let go
+                Some(Press::Long)
+            }
+            ButtonState::WaitingForSecondPress { .. } => {
+                self.state = ButtonState::Released;
+                Some(Press::Short)
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Turn is one click of a rotary encoder
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum Turn {
+    Clockwise,
+    Anticlockwise,
+}
+
+/// the states of the two lines of an encoder, as line A * 2 + line B, in the order they go through when it is turned clockwise
+const QUADRATURE_SEQUENCE: [u8; 4] = [0b11, 0b01, 0b00, 0b10];
+
+/// EncoderDecoder works out which way a quadrature rotary encoder is turned from the levels of its two lines.
+/// The lines change one at a time, so a change of both at once is a missed change, or a bounce, & is ignored.
+#[derive(Debug)]
+pub struct EncoderDecoder {
+    position: usize, // the position in QUADRATURE_SEQUENCE
+    steps: i16,      // the steps since the last click
+    steps_per_detent: i16,
+}
+
+impl EncoderDecoder {
+    pub fn new(steps_per_detent: u8) -> Self {
+        EncoderDecoder {
+            position: 0, // both lines are high while the knob is resting between clicks
+            steps: 0,
+            steps_per_detent: i16::from(steps_per_detent.max(1)),
+        }
+    }
+
+    /// lines_changed is called when either line changes, & returns the turn if a click has been completed
+    pub fn lines_changed(&mut self, line_a: bool, line_b: bool) -> Option<Turn> {
+        let state = u8::from(line_a) * 2 + u8::from(line_b);
+        let new_position = QUADRATURE_SEQUENCE
+            .iter()
+            .position(|&sequence_state| sequence_state == state)
+            .expect("every state is in the sequence");
+        let step = match (new_position + 4 - self.position) % 4 {
+            1 => 1,
+            3 => -1,
+            _ => 0, // no change, or both lines changed
+        };
+        self.position = new_position;
+        self.steps += step;
+        if self.steps >= self.steps_per_detent {
+            self.steps = 0;
+            Some(Turn::Clockwise)
+        } else if self.steps <= -self.steps_per_detent {
+            self.steps = 0;
+            Some(Turn::Anticlockwise)
+        } else {
+            None
+        }
+    }
+}
+
+/// GpioInput turns the line events from the GPIO lines into actions
+#[derive(Debug)]
+pub struct GpioInput {
+    debounce_time: Duration,
+    levels: HashMap<u32, bool>,        // the level of each line
+    last_edges: HashMap<u32, Instant>, // when each button's line last changed
+    buttons: Vec<(ButtonConfig, ButtonDecoder)>,
+    encoders: Vec<(EncoderConfig, EncoderDecoder)>,
+}
+
+impl GpioInput {
+    pub fn new(config: &GpioConfig) -> Self {
+        let double_press_time = Duration::from_millis(config.double_press_ms);
+        GpioInput {
+            debounce_time: Duration::from_millis(config.debounce_ms),
+            levels: HashMap::new(),
+            last_edges: HashMap::new(),
+            buttons: config
+                .buttons
+                .iter()
+                .map(|button| {
+                    let decoder = ButtonDecoder::new(
+                        Duration::from_millis(config.long_press_ms),
+                        button.double.map(|_| double_press_time),
+                    );
+                    (button.clone(), decoder)
+                })
+                .collect(),
+            encoders: config
+                .encoders
+                .iter()
+                .map(|encoder| {
+                    let decoder = EncoderDecoder::new(encoder.steps_per_detent);
+                    (encoder.clone(), decoder)
+                })
+                .collect(),
+        }
+    }
+
+    /// handle returns the actions caused by the line event
+    pub fn handle(&mut self, event: LineEvent) -> Vec<Action> {
+        let is_button = self
+            .buttons
+            .iter()
+            .any(|(button, _)| button.line == event.line);
+        if is_button {
+            // a button's contacts bounce, so changes soon after the last one are ignored
+            let bouncing = self.last_edges.get(&event.line).is_some_and(|last_edge| {
+                event.time.saturating_duration_since(*last_edge) < self.debounce_time
+            });
+            if bouncing || level(&self.levels, event.line) == event.high {
+                return Vec::new();
+            }
+            self.last_edges.insert(event.line, event.time);
+        }
+        self.levels.insert(event.line, event.high);
+
+        let mut actions = Vec::new();
+        for (button, decoder) in &mut self.buttons {
+            if button.line == event.line {
+                let pressed = event.high != button.active_low;
+                actions.extend(
+                    decoder
+                        .edge(pressed, event.time)
+                        .and_then(|press| button_action(button, press)),
+                );
+            }
+        }
+        for (encoder, decoder) in &mut self.encoders {
+            if encoder.line_a == event.line || encoder.line_b == event.line {
+                let turn = decoder.lines_changed(
+                    level(&self.levels, encoder.line_a),
+                    level(&self.levels, encoder.line_b),
+                );
+                actions.extend(turn.and_then(|turn| match turn {
+                    Turn::Clockwise => encoder.clockwise,
+                    Turn::Anticlockwise => encoder.anticlockwise,
+                }));
+            }
+        }
+        actions
+    }
+
+    /// deadline returns when timeout needs to be called next, if it does
+    pub fn deadline(&self) -> Option<Instant> {
+        self.buttons
+            .iter()
+            .filter_map(|(_, decoder)| decoder.deadline())
+            .min()
+    }
+
+    /// timeout returns the actions of the presses that are complete now that time has passed, eg a long press
+    pub fn timeout(&mut self, now: Instant) -> Vec<Action> {
+        self.buttons
+            .iter_mut()
+            .filter_map(|(button, decoder)| {
+                decoder
+                    .timeout(now)
+                    .and_then(|press| button_action(button, press))
+            })
+            .collect()
+    }
+}
+
+/// level returns the level of the line. Until it changes it is assumed to be high, as the lines are pulled up.
+fn level(levels: &HashMap<u32, bool>, line: u32) -> bool {
+    levels.get(&line).copied().unwrap_or(true)
+}
+
+/// button_action returns the action for the kind of press of the button
+fn button_action(button: &ButtonConfig, press: Press) -> Option<Action> {
+    match press {
+        Press::Short => button.short,
+        Press::Long => button.long.or(button.short),
+        Press::Double => button.double,
+    }
+}
+
+/// watch_lines starts watching the GPIO lines used by the controls, & returns the line events as they happen.
+/// Waiting for an edge blocks, so each line is watched by its own thread.
+pub fn watch_lines(config: &GpioConfig) -> anyhow::Result<mpsc::UnboundedReceiver<LineEvent>> {
+    use anyhow::Context;
+    use gpio_cdev::{Chip, EventRequestFlags, EventType, LineRequestFlags};
+
+    let mut chip =
+        Chip::new(&config.chip).with_context(|| format!("Failed to open {}", config.chip))?;
+    let (sender, receiver) = mpsc::unbounded_channel();
+    for line in config.lines() {
+        let line_events = chip
+            .get_line(line)
+            .and_then(|chip_line| {
+                chip_line.events(
+                    LineRequestFlags::INPUT,
+                    EventRequestFlags::BOTH_EDGES,
+                    "rradio_lcd_driver",
+                )
+            })
+            .with_context(|| format!("Failed to watch GPIO line {line}"))?;
+        let sender = sender.clone();
+        std::thread::spawn(move || {
+            for line_event in line_events {
+                let line_event = match line_event {
+                    Ok(line_event) => line_event,
+                    Err(error) => {
+                        println!(
+                            "Failed to read GPIO line {line}, so no longer watching it: {error}"
+                        );
+                        return;
+                    }
+                };
+                let event = LineEvent {
+                    line,
+                    high: line_event.event_type() == EventType::RisingEdge,
+                    time: Instant::now(),
+                };
+                if sender.send(event).is_err() {
+                    return; // nothing is interested in the line any more
+                }
+            }
+        });
+    }
+    Ok(receiver)
+}
+
+/// run turns the line events into actions & sends them to the driver, until there are no more line events
+pub async fn run(
+    mut input: GpioInput,
+    mut line_events: mpsc::UnboundedReceiver<LineEvent>,
+    actions: ActionSender,
+) {
+    loop {
+        let deadline = input.deadline();
+        let new_actions = tokio::select! {
+            line_event = line_events.recv() => match line_event {
+                Some(line_event) => input.handle(line_event),
+                None => return,
+            },
+            _ = tokio::time::sleep_until(deadline.unwrap_or_else(Instant::now)), if deadline.is_some() => {
+                input.timeout(Instant::now())
+            }
+        };
+        for action in new_actions {
+            actions.send(action);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// SimulatedLines stands in for the GPIO lines, producing the line events a real button or encoder would
+    struct SimulatedLines {
+        start: Instant,
+        events: Vec<LineEvent>,
+    }
+
+    impl SimulatedLines {
+        fn new() -> Self {
+            SimulatedLines {
+                start: Instant::now(),
+                events: Vec::new(),
+            }
+        }
+
+        fn at(&self, millis: u64) -> Instant {
+            self.start + Duration::from_millis(millis)
+        }
+
+        fn edge(&mut self, line: u32, high: bool, millis: u64) -> &mut Self {
+            let time = self.at(millis);
+            self.events.push(LineEvent { line, high, time });
+            self
+        }
+
+        /// press presses the active low button on the line at the time given, & releases it after the duration given
+        fn press(&mut self, line: u32, millis: u64, duration_ms: u64) -> &mut Self {
+            self.edge(line, false, millis)
+                .edge(line, true, millis + duration_ms)
+        }
+
+        /// turn turns the encoder on lines 5 & 6 by one click, starting at the time given
+        fn turn(&mut self, turn: Turn, millis: u64) -> &mut Self {
+            let sequence: Vec<u8> = match turn {
+                Turn::Clockwise => vec![0b01, 0b00, 0b10, 0b11],
+                Turn::Anticlockwise => vec![0b10, 0b00, 0b01, 0b11],
+            };
+            let mut levels = 0b11;
+            for (offset, state) in (0..).zip(sequence) {
+                let changed = levels ^ state;
+                let line = if changed == 0b10 { 5 } else { 6 };
+                self.edge(line, state & changed != 0, millis + offset);
+                levels = state;
+            }
+            self
+        }
+
+        /// actions feeds the line events to the input, calling timeout in between as run does, & returns the actions
+        fn actions(&self, input: &mut GpioInput, until_millis: u64) -> Vec<Action> {
+            let mut actions = Vec::new();
+            for event in &self.events {
+                while let Some(deadline) =
+                    input.deadline().filter(|deadline| *deadline <= event.time)
+                {
+                    actions.extend(input.timeout(deadline));
+                }
+                actions.extend(input.handle(*event));
+            }
+            while let Some(deadline) = input
+                .deadline()
+                .filter(|deadline| *deadline <= self.at(until_millis))
+            {
+                actions.extend(input.timeout(deadline));
+            }
+            actions
+        }
+    }
+
+    fn config() -> GpioConfig {
+        toml::from_str(
+            r#"
+            [[buttons]]
+            line = 13
+            short = "mute"
+            long = "menu"
+            double = "next_page"
+
+            [[buttons]]
+            line = 19
+            short = "station_up"
+
+            [[encoders]]
+            line_a = 5
+            line_b = 6
+            clockwise = "volume_up"
+            anticlockwise = "volume_down"
+            "#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn presses_are_told_apart() {
+        let mut lines = SimulatedLines::new();
+        lines
+            .press(13, 0, 100) // short
+            .press(13, 1000, 1500) // long
+            .press(13, 3000, 100) // double
+            .press(13, 3200, 100)
+            .press(19, 5000, 1500); // long, but the button only has a short action
+        assert_eq!(
+            lines.actions(&mut GpioInput::new(&config()), 10_000),
+            vec![
+                Action::Mute,
+                Action::Menu,
+                Action::NextPage,
+                Action::StationUp
+            ]
+        );
+    }
+
+    #[test]
+    fn a_long_press_happens_while_the_button_is_held() {
+        let mut lines = SimulatedLines::new();
+        lines.edge(13, false, 0);
+        assert_eq!(lines.actions(&mut GpioInput::new(&config()), 799), vec![]);
+        assert_eq!(
+            lines.actions(&mut GpioInput::new(&config()), 800),
+            vec![Action::Menu]
+        );
+    }
+
+    #[test]
+    fn bounces_are_ignored() {
+        let mut lines = SimulatedLines::new();
+        lines
+            .edge(19, false, 0)
+            .edge(19, true, 2)
+            .edge(19, false, 4)
+            .edge(19, true, 100)
+            .edge(19, false, 103)
+            .edge(19, true, 105);
+        assert_eq!(
+            lines.actions(&mut GpioInput::new(&config()), 1000),
+            vec![Action::StationUp]
+        );
+    }
+
+    #[test]
+    fn encoder_turns_are_decoded() {
+        let mut lines = SimulatedLines::new();
+        lines
+            .turn(Turn::Clockwise, 0)
+            .turn(Turn::Clockwise, 100)
+            .turn(Turn::Anticlockwise, 200);
+        assert_eq!(
+            lines.actions(&mut GpioInput::new(&config()), 1000),
+            vec![Action::VolumeUp, Action::VolumeUp, Action::VolumeDown]
+        );
+
+        // a change of both lines at once is ignored, so the click is not completed
+        let mut decoder = EncoderDecoder::new(4);
+        assert_eq!(decoder.lines_changed(false, true), None);
+        assert_eq!(decoder.lines_changed(true, false), None);
+        assert_eq!(decoder.lines_changed(true, true), None);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn run_sends_the_actions_to_the_driver() {
+        let (line_sender, line_events) = mpsc::unbounded_channel();
+        let (actions, mut action_receiver) = super::super::channel();
+        let input = tokio::spawn(run(GpioInput::new(&config()), line_events, actions));
+
+        let now = Instant::now();
+        line_sender
+            .send(LineEvent {
+                line: 13,
+                high: false,
+                time: now,
+            })
+            .unwrap();
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        line_sender
+            .send(LineEvent {
+                line: 13,
+                high: true,
+                time: now + Duration::from_millis(100),
+            })
+            .unwrap();
+        let action_event = action_receiver.recv().await.unwrap(); // once it is clear that it is not a double press
+        assert_eq!(action_event.action, Action::Mute);
+        assert_eq!(action_event.echo, None);
+        drop(line_sender);
+        input.await.unwrap();
+    }
+}
@@ -0,0 +1,162 @@
+//! Input from controls attached to the Pi that drives the screen, eg buttons & rotary encoders on GPIO lines,
+//! & IR remotes & USB keypads. Whatever the control, what it asks for is an Action, which the driver carries out.
+
+use rradio_messages::Command;
+use serde::Deserialize;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tokio::time::Instant;
+
+pub mod evdev;
+pub mod gpio;
+
+/// how long after one digit of a channel the next has to be typed, before the first is forgotten
+const CHANNEL_ENTRY_TIME: Duration = Duration::from_secs(3);
+
+/// Action is what a control asks for
+#[derive(PartialEq, Eq, Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Action {
+    VolumeUp,
+    VolumeDown,
+    Mute, // mutes if not muted, & unmutes if muted
+    StationUp,
+    StationDown,
+    NextPage,
+    Menu,
+    Digit(u8), // one digit of a channel, eg from a remote, written as `{ digit = 7 }` in the config file
+    ShutDown,  // asks whether to shut the computer down
+    Reboot,    // asks whether to reboot the computer
+}
+
+impl Action {
+    /// command returns the command to send to rradio for the action, or None if the action is carried out by the driver itself.
+    /// The current channel is needed to work out the channel to go to for station up & down.
+    pub fn command(self, current_channel: &str) -> Option<Command> {
+        match self {
+            Action::VolumeUp => Some(Command::VolumeUp),
+            Action::VolumeDown => Some(Command::VolumeDown),
+            Action::Mute => Some(Command::ToggleIsMuted),
+            Action::StationUp => Some(Command::SetChannel(next_channel(current_channel, 1))),
+            Action::StationDown => Some(Command::SetChannel(next_channel(current_channel, -1))),
+            Action::NextPage
+            | Action::Menu
+            | Action::Digit(_)
+            | Action::ShutDown
+            | Action::Reboot => None,
+        }
+    }
+}
+
+/// next_channel returns the channel the specified number of channels on from the current one.
+/// Channels are numbered from 00 to 99, & going up from 99 goes round to 00.
+fn next_channel(current_channel: &str, offset: i32) -> String {
+    let current_channel: i32 = current_channel.parse().unwrap_or(0); // eg when nothing has been played yet
+    format!("{:02}", (current_channel + offset).rem_euclid(100))
+}
+
+/// ChannelEntry collects the two digits of a channel as they are typed, eg on a remote
+#[derive(Debug, Default)]
+pub struct ChannelEntry {
+    digits: String,
+    last_digit_time: Option<Instant>,
+}
+
+impl ChannelEntry {
+    /// digit adds the digit, & returns the channel once both digits have been typed. If the last digit was typed too long ago,
+    /// or completed a channel, this digit starts a new channel.
+    pub fn digit(&mut self, digit: u8, now: Instant) -> Option<String> {
+        let timed_out = self.last_digit_time.map_or(true, |last_digit_time| {
+            now.saturating_duration_since(last_digit_time) >= CHANNEL_ENTRY_TIME
+        });
+        if timed_out || self.digits.len() == 2 {
+            self.digits.clear();
+        }
+        self.digits.push(char::from(b'0' + digit % 10));
+        self.last_digit_time = Some(now);
+        if self.digits.len() == 2 {
+            Some(self.digits.clone())
+        } else {
+            None
+        }
+    }
+
+    /// typed returns the digits typed so far, with an underline for each digit still to be typed, eg "4_"
+    pub fn typed(&self) -> String {
+        format!("{:_<2}", self.digits)
+    }
+}
+
+/// ActionEvent is an action from a control, with what to echo on the screen, eg the name of the key pressed on a remote
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct ActionEvent {
+    pub action: Action,
+    pub echo: Option<String>,
+}
+
+/// ActionSender sends the actions from a control to the driver. It can be cloned, so that each kind of control can have its own.
+#[derive(Debug, Clone)]
+pub struct ActionSender(mpsc::UnboundedSender<ActionEvent>);
+
+impl ActionSender {
+    /// send passes the action to the driver. Actions done while the driver is not connected to rradio are dropped.
+    pub fn send(&self, action: Action) {
+        self.send_event(ActionEvent { action, echo: None });
+    }
+
+    /// send_echoed passes the action to the driver, which briefly shows the echo, so that it is clear what has been pressed
+    pub fn send_echoed(&self, action: Action, echo: String) {
+        self.send_event(ActionEvent {
+            action,
+            echo: Some(echo),
+        });
+    }
+
+    fn send_event(&self, action_event: ActionEvent) {
+        if let Err(error) = self.0.send(action_event) {
+            println!(
+                "The driver has stopped, so {:?} was not done",
+                error.0.action
+            );
+        }
+    }
+}
+
+/// channel returns a sender for the controls & the receiver the driver takes the actions from
+pub fn channel() -> (ActionSender, mpsc::UnboundedReceiver<ActionEvent>) {
+    let (sender, receiver) = mpsc::unbounded_channel();
+    (ActionSender(sender), receiver)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn station_up_and_down_go_round() {
+        let channel = |action: Action, current_channel| match action.command(current_channel) {
+            Some(Command::SetChannel(channel)) => channel,
+            other => panic!("expected a channel, got {:?}", other),
+        };
+        assert_eq!(channel(Action::StationUp, "07"), "08");
+        assert_eq!(channel(Action::StationUp, "99"), "00");
+        assert_eq!(channel(Action::StationDown, "00"), "99");
+        assert_eq!(channel(Action::StationUp, ""), "01");
+        assert!(Action::NextPage.command("07").is_none());
+    }
+
+    #[test]
+    fn a_channel_is_typed_as_two_digits() {
+        let start = Instant::now();
+        let mut channel_entry = ChannelEntry::default();
+        assert_eq!(channel_entry.digit(4, start), None);
+        assert_eq!(channel_entry.typed(), "4_");
+        assert_eq!(channel_entry.digit(2, start), Some("42".to_string()));
+        assert_eq!(channel_entry.typed(), "42");
+
+        // a digit on its own is forgotten after a while
+        assert_eq!(channel_entry.digit(1, start), None);
+        assert_eq!(channel_entry.digit(7, start + CHANNEL_ENTRY_TIME), None);
+        assert_eq!(channel_entry.typed(), "7_");
+    }
+}
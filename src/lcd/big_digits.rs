@@ -0,0 +1,76 @@
+//! Big digits, two lines tall & three characters wide, so that a number can be read from across the room, eg the volume
+//! while it is being changed. They are made from the full block in the screen's own character set & three bespoke
+//! characters, which Lc loads into the screen in place of the accented letters while there are big digits on the screen.
+
+/// the full block, which is in the screen's own character set
+pub const FULL_BLOCK: char = '█';
+/// the top three rows of a character, a bespoke character
+pub const UPPER_BLOCK: char = '▀';
+/// the bottom three rows of a character, a bespoke character
+pub const LOWER_BLOCK: char = '▄';
+/// the top & bottom three rows of a character, a bespoke character. In the top half of a digit, the bottom rows make the middle bar.
+pub const UPPER_AND_LOWER_BLOCKS: char = '≡';
+
+/// how many characters wide each big digit is
+pub const BIG_DIGIT_WIDTH: usize = 3;
+
+/// the top & bottom halves of each digit from 0 to 9
+const BIG_DIGITS: [[&str; 2]; 10] = [
+    ["█▀█", "█▄█"],
+    ["▀█ ", "▄█▄"],
+    ["≡≡█", "█▄▄"],
+    ["≡≡█", "▄▄█"],
+    ["█▄█", "  █"],
+    ["█≡≡", "▄▄█"],
+    ["█≡≡", "█▄█"],
+    ["▀▀█", "  █"],
+    ["█≡█", "█▄█"],
+    ["█≡█", "▄▄█"],
+];
+
+/// is_bespoke is true if the character is one of the bespoke characters used by the big digits
+pub fn is_bespoke(one_char: char) -> bool {
+    matches!(one_char, UPPER_BLOCK | LOWER_BLOCK | UPPER_AND_LOWER_BLOCKS)
+}
+
+/// big_number returns the top & bottom lines of the number in big digits, with a space between the digits.
+/// Anything that is not a digit is left out.
+pub fn big_number(number: &str) -> [String; 2] {
+    let digits: Vec<&[&str; 2]> = number
+        .chars()
+        .filter_map(|one_char| one_char.to_digit(10))
+        .map(|digit| &BIG_DIGITS[digit as usize])
+        .collect();
+    let half = |index: usize| {
+        digits
+            .iter()
+            .map(|big_digit| big_digit[index])
+            .collect::<Vec<&str>>()
+            .join(" ")
+    };
+    [half(0), half(1)]
+}
+
+/// big_number_width returns how many characters wide the number is in big digits
+pub fn big_number_width(digit_count: usize) -> usize {
+    (digit_count * (BIG_DIGIT_WIDTH + 1)).saturating_sub(1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn numbers_are_two_lines_tall() {
+        assert_eq!(
+            big_number("42"),
+            ["█▄█ ≡≡█".to_string(), "  █ █▄▄".to_string()]
+        );
+        assert_eq!(
+            big_number("07").map(|half| half.chars().count()),
+            [big_number_width(2); 2]
+        );
+        assert!(big_number("42")[1].chars().any(is_bespoke));
+        assert_eq!(big_number(""), [String::new(), String::new()]);
+    }
+}
@@ -1,82 +1,119 @@
-pub const BITMAPS: [[u8; 8]; 8] = [
-    [
-        0b10000, //pattern for topmost row. the 1 specifies that the top left pixel is on, the other zeroes specify that the other topmost pixels are off
-        0b10000, //
-        0b10000, //
-        0b10000, //
-        0b10000, //
-        0b10000, //
-        0b10000, //
-        0b11111,
-    ], //
-    [
-        0b01000, //
-        0b01000, //
-        0b01000, //
-        0b01000, //
-        0b01000, //
-        0b01000, //
-        0b01000, //
-        0b11111,
-    ],
-    [
-        0b00100, //
-        0b00100, //
-        0b00100, //
-        0b00100, //
-        0b00100, //
-        0b00100, //
-        0b00100, //
-        0b11111,
-    ],
-    [
-        0b00010, //
-        0b00010, //
-        0b00010, //
-        0b00010, //
-        0b00010, //
-        0b00010, //
-        0b00010, //
-        0b11111,
-    ],
-    [
-        0b00001, //
-        0b00001, //
-        0b00001, //
-        0b00001, //
-        0b00001, //
-        0b00001, //
-        0b00001, //
-        0b11111,
-    ],
-    [
-        0b01100, // pattern for topmost row for e accute
-        0b10000, // this pattern specifies that the left-most bit is on, & the other 4 are off on the top but one row.
-        0b01110, //
-        0b10001, //
-        0b11111, //
-        0b10000, //
-        0b01110, //
-        0b00000, // bottom row, which is expected to be all zeros
-    ],
-    [
-        0b00110, // e grave pattern
-        0b00001, //
-        0b01110, //
-        0b10001, //
-        0b11111, //
-        0b10000, //
-        0b01110, //
-        0b00000,
-    ],
-    [
-        0b00110, // a grave pattern
-        0b00001, //
-        0b01110, //
-        0b00001, //
-        0b01111, //
-        0b10001, //
-        0b01111, //
-        0b00000,
-    ],
-];
+pub const BITMAPS: [[u8; 8]; 8] = [
+    [
+        0b10000, //pattern for topmost row. the 1 specifies that the top left pixel is on, the other zeroes specify that the other topmost pixels are off
+        0b10000, //
+        0b10000, //
+        0b10000, //
+        0b10000, //
+        0b10000, //
+        0b10000, //
+        0b11111,
+    ], //
+    [
+        0b01000, //
+        0b01000, //
+        0b01000, //
+        0b01000, //
+        0b01000, //
+        0b01000, //
+        0b01000, //
+        0b11111,
+    ],
+    [
+        0b00100, //
+        0b00100, //
+        0b00100, //
+        0b00100, //
+        0b00100, //
+        0b00100, //
+        0b00100, //
+        0b11111,
+    ],
+    [
+        0b00010, //
+        0b00010, //
+        0b00010, //
+        0b00010, //
+        0b00010, //
+        0b00010, //
+        0b00010, //
+        0b11111,
+    ],
+    [
+        0b00001, //
+        0b00001, //
+        0b00001, //
+        0b00001, //
+        0b00001, //
+        0b00001, //
+        0b00001, //
+        0b11111,
+    ],
+    [
+        0b01100, // pattern for topmost row for e accute
+        0b10000, // this pattern specifies that the left-most bit is on, & the other 4 are off on the top but one row.
+        0b01110, //
+        0b10001, //
+        0b11111, //
+        0b10000, //
+        0b01110, //
+        0b00000, // bottom row, which is expected to be all zeros
+    ],
+    [
+        0b00110, // e grave pattern
+        0b00001, //
+        0b01110, //
+        0b10001, //
+        0b11111, //
+        0b10000, //
+        0b01110, //
+        0b00000,
+    ],
+    [
+        0b00110, // a grave pattern
+        0b00001, //
+        0b01110, //
+        0b00001, //
+        0b01111, //
+        0b10001, //
+        0b01111, //
+        0b00000,
+    ],
+];
+
+/// the first of the bespoke characters that are swapped for the big digit bitmaps while there are
+/// big digits on the screen, as there are only 8 bespoke characters
+pub const FIRST_SWAPPED_CHARACTER: usize = 5;
+/// the upper block, the lower block & both, which the big digits are made from
+pub const BIG_DIGIT_BITMAPS: [[u8; 8]; 3] = [
+    [
+        0b11111, // upper block, the top three rows
+        0b11111, //
+        0b11111, //
+        0b00000, //
+        0b00000, //
+        0b00000, //
+        0b00000, //
+        0b00000,
+    ],
+    [
+        0b00000, // lower block, the bottom three rows
+        0b00000, //
+        0b00000, //
+        0b00000, //
+        0b00000, //
+        0b11111, //
+        0b11111, //
+        0b11111,
+    ],
+    [
+        0b11111, // upper & lower blocks, the top & middle bars in the top half of a digit
+        0b11111, //
+        0b11111, //
+        0b00000, //
+        0b00000, //
+        0b11111, //
+        0b11111, //
+        0b11111,
+    ],
+];
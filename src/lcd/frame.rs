@@ -0,0 +1,79 @@
+use super::{LineNum, NUM_CHARACTERS_PER_LINE};
+
+const NUM_LINES: usize = 4;
+
+/// Frame is the complete contents of the screen, 4 lines of 20 characters.
+/// It is built up in memory, then written to the screen in one go, so that what is shown can be tested without a screen.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct Frame {
+    lines: [[char; NUM_CHARACTERS_PER_LINE]; NUM_LINES],
+}
+
+impl Default for Frame {
+    fn default() -> Self {
+        Frame {
+            lines: [[' '; NUM_CHARACTERS_PER_LINE]; NUM_LINES],
+        }
+    }
+}
+
+impl Frame {
+    /// new returns a blank frame
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// from_lines returns a frame containing the 4 lines specified, each shortened or lengthened to fill the line
+    pub fn from_lines(lines: &[String; NUM_LINES]) -> Self {
+        let mut frame = Frame::new();
+        for (line_number, line) in LineNum::ALL.iter().zip(lines.iter()) {
+            frame.write_multiline(*line_number, NUM_CHARACTERS_PER_LINE, line);
+        }
+        frame
+    }
+
+    /// write writes the string to the line starting at the specified column, like Lc::write_ascii.
+    /// Any characters that do not fit on the line are dropped.
+    pub fn write(&mut self, line_number: LineNum, column: usize, string: &str) {
+        let line = &mut self.lines[line_number.into_u16() as usize];
+        for (cell, one_char) in line.iter_mut().skip(column).zip(string.chars()) {
+            *cell = one_char;
+        }
+    }
+
+    /// write_multiline writes exactly the specified number of characters starting at the beginning of the line, like Lc::write_multiline.
+    /// The string is padded with spaces or truncated to the length, which can be more than one line long.
+    pub fn write_multiline(&mut self, line_number: LineNum, length: usize, string: &str) {
+        let start = line_number.into_u16() as usize * NUM_CHARACTERS_PER_LINE;
+        let mut chars = string.chars();
+        for position in start..(start + length).min(NUM_LINES * NUM_CHARACTERS_PER_LINE) {
+            self.lines[position / NUM_CHARACTERS_PER_LINE][position % NUM_CHARACTERS_PER_LINE] =
+                chars.next().unwrap_or(' ');
+        }
+    }
+
+    /// boxed_lines returns the lines with a bar at each end, so that a virtual screen can be seen in the terminal
+    pub fn boxed_lines(&self) -> String {
+        let lines: Vec<String> = LineNum::ALL
+            .iter()
+            .map(|line_number| format!("|{}|", self.line(*line_number)))
+            .collect();
+        lines.join("\n")
+    }
+
+    /// line returns the specified line as a string that is exactly one line long
+    pub fn line(&self, line_number: LineNum) -> String {
+        self.lines[line_number.into_u16() as usize].iter().collect()
+    }
+}
+
+impl std::fmt::Display for Frame {
+    /// writes the 4 lines separated by new lines, which is handy when debugging
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let lines: Vec<String> = LineNum::ALL
+            .iter()
+            .map(|line_number| self.line(*line_number))
+            .collect();
+        write!(f, "{}", lines.join("\n"))
+    }
+}
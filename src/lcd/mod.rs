@@ -5,15 +5,22 @@ The lines below instruct the Pi to use the hd44780 driver & load it
     dtparam=pin_d4=24,pin_d5=23,pin_d6=25,pin_d7=9
 */
 use anyhow::Context;
-use chrono::Local;
-use rradio_messages::PipelineState;
 use std::io::Write;
 
+use crate::localisation::{text, MessageId};
+
+pub mod big_digits;
 mod character_pattern;
-mod get_temperature;
-mod get_wifi_strength;
+pub mod frame;
+pub mod get_temperature;
+pub mod get_wifi_strength;
+
+pub use frame::Frame;
 
-#[derive(PartialEq, Debug)]
+/// SharedScreen is a copy of what is on the screen that can be read while the driver is running, eg by a test
+pub type SharedScreen = std::sync::Arc<std::sync::Mutex<Frame>>;
+
+#[derive(PartialEq, Debug, Clone, Copy)]
 pub enum LineNum {
     Line1,
     Line2,
@@ -28,7 +35,15 @@ pub const LINE1_DATA_CHAR_COUNT: u16 = (NUM_CHARACTERS_PER_LINE - VOLUME_CHAR_CO
 pub const LINE1_DATA_CHAR_COUNT_USIZE: usize = LINE1_DATA_CHAR_COUNT as usize;
 
 impl LineNum {
-    fn into_u16(self) -> u16 {
+    /// all the lines, from top to bottom
+    pub const ALL: [LineNum; 4] = [
+        LineNum::Line1,
+        LineNum::Line2,
+        LineNum::Line3,
+        LineNum::Line4,
+    ];
+
+    pub(crate) fn into_u16(self) -> u16 {
         match self {
             LineNum::Line1 => 0,
             LineNum::Line2 => 1,
@@ -38,8 +53,15 @@ impl LineNum {
     }
 }
 
+/// Lc is the LCD screen. It can also be a virtual screen, which is used when there is no LCD screen,
+/// eg when replaying a recording on a laptop or when testing. Either way it keeps a copy of what is on the screen.
 pub struct Lc {
-    lcd_file: std::fs::File,
+    lcd_file: Option<std::fs::File>, // None if the screen is virtual
+    screen: Frame,
+    print_changes: bool, // true if a virtual screen is printed whenever a new frame is shown
+    shared_screen: Option<SharedScreen>,
+    cursor: Option<(LineNum, usize)>, // where the blinking cursor is, or None if it is off
+    big_digits_loaded: bool, // true if the bespoke characters for the big digits are loaded in place of the accented letters
 }
 
 impl Lc {
@@ -102,26 +124,82 @@ impl Lc {
         Self::clear_screen(&lcd_file);
         //println!("Initialised the LCD screen");
 
-        Ok(Lc { lcd_file })
+        Ok(Lc {
+            lcd_file: Some(lcd_file),
+            screen: Frame::new(),
+            print_changes: false,
+            shared_screen: None,
+            cursor: None,
+            big_digits_loaded: false,
+        })
+    }
+
+    /// new_virtual returns a screen that only exists in memory. If print_changes is true,
+    /// each new frame that is shown is printed, so that what the screen would show can be seen.
+    pub fn new_virtual(print_changes: bool) -> Self {
+        Lc {
+            lcd_file: None,
+            screen: Frame::new(),
+            print_changes,
+            shared_screen: None,
+            cursor: None,
+            big_digits_loaded: false,
+        }
+    }
+
+    /// screen returns what is on the screen
+    pub fn screen(&self) -> &Frame {
+        &self.screen
+    }
+
+    /// share_screen returns a copy of what is on the screen, which is kept up to date as the screen changes
+    pub fn share_screen(&mut self) -> SharedScreen {
+        let screen = &self.screen;
+        self.shared_screen
+            .get_or_insert_with(|| SharedScreen::new(std::sync::Mutex::new(screen.clone())))
+            .clone()
+    }
+
+    /// update_shared_screen copies the screen to the shared copy, if there is one
+    fn update_shared_screen(&self) {
+        if let Some(shared_screen) = &self.shared_screen {
+            *shared_screen.lock().unwrap() = self.screen.clone();
+        }
     }
 
     pub fn clear(&mut self) {
-        Self::clear_screen(&mut self.lcd_file);
+        self.screen = Frame::new();
+        self.cursor = None; // clearing the screen turns the cursor off
+        self.big_digits_loaded = false; // clearing the screen loads the accented letters again
+        self.update_shared_screen();
+        if let Some(lcd_file) = &mut self.lcd_file {
+            Self::clear_screen(lcd_file);
+        }
     }
 
     /// write_ascii writes the specified string to the line & column specified. It is assumed that the characters are ASCII.
     /// The characters must not be too long to fit on the specified line in the specified position
     pub fn write_ascii(&mut self, line_number: LineNum, column: u16, input: &str) {
+        self.screen.write(line_number, column as usize, input);
+        self.update_shared_screen();
+        let Some(lcd_file) = &mut self.lcd_file else {
+            return;
+        };
         let line_number = line_number.into_u16();
-        if let Err(err) = write!(self.lcd_file, "\x1b[Lx{column}y{line_number};{}", input) {
+        if let Err(err) = write!(lcd_file, "\x1b[Lx{column}y{line_number};{}", input) {
             println!("in write_ascii, Failed to write to LCD screen : {err}");
         }
     }
     /// write_multiline writes exactly the specified number of characters, which can be less than one line
     /// It any character is not ASCII, it is transliterated.
     pub fn write_multiline(&mut self, line_number: LineNum, length: usize, in_string: &str) {
+        self.screen.write_multiline(line_number, length, in_string);
+        self.update_shared_screen();
+        let Some(lcd_file) = &mut self.lcd_file else {
+            return;
+        };
         let line_number = line_number.into_u16(); // convert the line number from an enum to u16
-        if let Err(err) = write!(self.lcd_file, "\x1b[Lx0y{line_number};") {
+        if let Err(err) = write!(lcd_file, "\x1b[Lx0y{line_number};") {
             // move the cursor to the start of the specified line
             println!("in write_multiline, Failed to write move the cursor : {err}");
         }
@@ -132,6 +210,13 @@ impl Lc {
                 output_string.push(one_char as u8);
             } else {
                 output_string.extend_from_slice(match one_char {
+                    'é' | 'è' | 'à' if self.big_digits_loaded => {
+                        unidecode::unidecode_char(one_char).as_bytes() // the big digits are using the bespoke characters
+                    }
+                    big_digits::UPPER_BLOCK if self.big_digits_loaded => &[5],
+                    big_digits::LOWER_BLOCK if self.big_digits_loaded => &[6],
+                    big_digits::UPPER_AND_LOWER_BLOCKS if self.big_digits_loaded => &[7],
+                    big_digits::FULL_BLOCK => &[0xFF],
                     'é' => &[5], // e accute fifth bespoke character defined starting with the zeroeth bespoke character
                     'è' => &[6], // e grave
                     'à' => &[7], // a grave
@@ -152,164 +237,180 @@ impl Lc {
 
         let mut lines = output_string.chunks_exact(NUM_CHARACTERS_PER_LINE);
         for line in lines.by_ref() {
-            self.lcd_file.write_all(line).expect("Failed in write_all");
-            self.lcd_file
+            lcd_file.write_all(line).expect("Failed in write_all");
+            lcd_file
                 .write_all(b"\n")
                 .expect("Failed in new line in write_all");
         }
-        self.lcd_file
+        lcd_file
             .write_all(lines.remainder())
             .expect("Failed to write rest in write_all");
     }
 
-    /// write_volume outputs the volume (or the gstreamer state if not playing, or "  Muted") to the LCD screen
-    pub fn write_volume(&mut self, pipe_line_state: PipelineState, is_muted: bool, volume: i32) {
-        //println!("in function write_volume: state {}", pipe_line_state);
-
-        let message = if (pipe_line_state == PipelineState::Playing) && volume >= 0 {
-            if is_muted {
-                "  Muted".to_string() // 2 spaces in order to right justify
-            } else {
-                format!(
-                    "Vol{:>Width$.Width$}",
-                    volume,
-                    Width = VOLUME_CHAR_COUNT - 3
-                )
-            }
-        } else {
-            format!(
-                "{:<Width$.Width$}",
-                pipe_line_state.to_string(),
-                Width = VOLUME_CHAR_COUNT
-            ) // if we use pipeline_state.to_string() without the .to_string, the result can be less than 7 characters long
-        };
-        self.write_ascii(LineNum::Line1, LINE1_DATA_CHAR_COUNT, message.as_str())
-    }
-
-    /// get_cpu_temperature gets the CPU temperature as an integer
-    pub fn get_cpu_temperature(&mut self) -> i32 {
-        get_temperature::get_cpu_temperature()
-    }
     /// write_temperature_and_strength writes the CPU temperature & the Wi-Fi signal strength to the specified line
     pub fn write_temperature_and_strength(&mut self, line_number: LineNum) {
         self.write_multiline(
             line_number,
             NUM_CHARACTERS_PER_LINE,
-            &format!(
-                "CPU Temp {}C WiFi{}",
-                get_temperature::get_cpu_temperature(),
-                get_wifi_strength::get_wifi_signal_strength()
+            &text(
+                MessageId::TemperatureAndStrength,
+                &[
+                    &get_temperature::get_cpu_temperature(),
+                    &get_wifi_strength::get_wifi_signal_strength(),
+                ],
             ),
         )
     }
 
-    ///   write_date_and_time_of_day_line3 writes the date & time to line 3 of the screen
-    pub fn write_date_and_time_of_day_line3(&mut self) {
-        // writes the time of day to line 3
-        self.write_multiline(
-            LineNum::Line3,
-            NUM_CHARACTERS_PER_LINE,
-            Local::now()
-                .format("  %d %b %y %H:%M:%SS")
-                .to_string()
-                .as_str(),
-        )
+    /// cursor returns where the blinking cursor is, or None if it is off
+    pub fn cursor(&self) -> Option<(LineNum, usize)> {
+        self.cursor
     }
 
-    /// write_temperature_and_time_to_line4 writes the temperature & time to line 4
-    pub fn write_temperature_and_time_to_line4(&mut self) {
-        self.write_multiline(
-            LineNum::Line4,
-            NUM_CHARACTERS_PER_LINE,
-            &format!(
-                "CPU temp {} C {}",
-                get_temperature::get_cpu_temperature(),
-                Local::now().format("%H:%M").to_string().as_str(),
+    /// show_cursor puts the blinking cursor at the line & column specified, or turns it off if None.
+    /// Writing to the screen moves the cursor, so it has to be put back after each frame is shown.
+    pub fn show_cursor(&mut self, position: Option<(LineNum, usize)>) {
+        let was_on = self.cursor.is_some();
+        self.cursor = position;
+        let Some(lcd_file) = &mut self.lcd_file else {
+            return;
+        };
+        let written = match position {
+            Some((line_number, column)) => write!(
+                lcd_file,
+                "\x1b[Lx{column}y{};\x1b[LC\x1b[LB",
+                line_number.into_u16()
             ),
-        )
+            None if was_on => write!(lcd_file, "\x1b[Lc\x1b[Lb"),
+            None => Ok(()),
+        };
+        if let Err(err) = written {
+            println!("in show_cursor, Failed to write to LCD screen : {err}");
+        }
     }
 
-    /// write_all_line_2 takes the specifed string & shortens it or lengthens it to exactly fill line 2
-    pub fn write_all_line_2(&mut self, string: &str) {
-        self.write_multiline(LineNum::Line2, NUM_CHARACTERS_PER_LINE, string);
+    /// set_backlight turns the backlight on or off
+    pub fn set_backlight(&mut self, on: bool) {
+        let Some(lcd_file) = &mut self.lcd_file else {
+            return;
+        };
+        if let Err(err) = write!(lcd_file, "{}", if on { "\x1b[L+" } else { "\x1b[L-" }) {
+            println!("in set_backlight, Failed to write to LCD screen : {err}");
+        }
     }
 
-    /// write_buffer_state writes a cursor to line 4 showing how full the gsteamer buffer is
-    pub fn write_buffer_state(&mut self, buffer_position: u8) {
-        // writes the state of the gstreamer buffer on the 4th line as a moving cursor
-        if let Err(err) = write!(self.lcd_file, "\x1b[Lx0y3;") {
-            // move the cursor to the start of the specified line
-            println!("in write_buffer_state, Failed to write move the cursor : {err}");
-        }
-        let trimmed_buffer = buffer_position.min(99); // 0 to 100 is 101 values, & the screen only handles 100 values, so trim downwards
-        #[allow(clippy::cast_possible_wrap)]
-        let scaled_buffer = (trimmed_buffer / 5) as i8; // the characters have 5 columns
-        for _count in 0..scaled_buffer {
-            if let Err(err) = write!(self.lcd_file, " ") {
-                // first write space in all the character positions before the cursor
-                println!("in write_buffer_state, Failed to write space before the cursor : {err}");
-            }
+    /// load_bespoke_characters loads the bespoke characters for the big digits if there are any big digits in the frame,
+    /// otherwise the accented letters, unless they are loaded already
+    fn load_bespoke_characters(&mut self, frame: &Frame) {
+        let wants_big_digits = LineNum::ALL
+            .iter()
+            .any(|line_number| frame.line(*line_number).chars().any(big_digits::is_bespoke));
+        if wants_big_digits == self.big_digits_loaded {
+            return;
         }
-        self.lcd_file
-            .write_all(&[(trimmed_buffer % 5)])
-            .expect("in write_buffer_state, Failed to write the cursor");
-
-        for _count in scaled_buffer + 1..20 {
-            if let Err(err) = write!(self.lcd_file, " ") {
-                // then clear the rest of the line
-                println!("in write_buffer_state, Failed to write space after the cursor : {err}");
+        self.big_digits_loaded = wants_big_digits;
+        let Some(lcd_file) = &mut self.lcd_file else {
+            return;
+        };
+        let first = character_pattern::FIRST_SWAPPED_CHARACTER;
+        let bitmaps = if wants_big_digits {
+            &character_pattern::BIG_DIGIT_BITMAPS[..]
+        } else {
+            &character_pattern::BITMAPS[first..]
+        };
+        for (char_count, bitmap) in (first..).zip(bitmaps) {
+            let hex: String = bitmap.iter().map(|row| format!("{:02x}", row)).collect();
+            if let Err(err) = write!(lcd_file, "\x1b[LG{:01x}{};", char_count, hex) {
+                println!("in load_bespoke_characters, Failed to write to LCD screen : {err}");
             }
         }
     }
 
-    /// write_with_scroll writes with a scroll, as appropriate the string with the wanted scroll value to the screen
-    ///
-    pub fn write_with_scroll(
-        &mut self,
-        line: LineNum,        // the line to write to
-        length: usize, // length of the screen  to be used; typically a multiple of the line length
-        string: &str,  // The string to write
-        position: &mut usize, // the scroll position; it is mutable so that sucessive calls scroll the line.
-    ) {
-        if string.is_char_boundary(*position) {
-            // check that the preceding decrements of position have solved the char boundary problem
-            self.write_multiline(line, length, &string[*position..]); // as they have, we can safely call this line
+    /// show_frame writes the whole frame to the screen, one line at a time
+    pub fn show_frame(&mut self, frame: &Frame) {
+        self.load_bespoke_characters(frame);
+        if self.print_changes && self.screen != *frame {
+            println!(
+                "+{0}+\n{1}\n+{0}+",
+                "-".repeat(NUM_CHARACTERS_PER_LINE),
+                frame.boxed_lines()
+            );
         }
-        let mut found_space = false;
-        for i in *position + 6..*position + 14 {
-            // scroll to the next space, if it is reasonably soon, but not too soon
-            if i + 1 < string.len() {
-                // note "string.len() -1) is largw and positive if the string is null
-                // check the index stays within bounds
-                if string.as_bytes()[i] == b' ' {
-                    *position = i;
-                    found_space = true;
-                    break;
-                }
-            } else {
-                println!(
-                    "Breaking to avoid going beyond end of string, which is \"{}\"",
-                    string
-                );
+        for line_number in LineNum::ALL {
+            self.write_multiline(
+                line_number,
+                NUM_CHARACTERS_PER_LINE,
+                &frame.line(line_number),
+            );
+        }
+    }
+}
+
+/// set_brightness sets the brightness of the backlight, as a percentage of the maximum, by writing to the brightness file
+/// of a backlight driven by PWM, eg /sys/class/backlight/lcd/brightness. The maximum is read from max_brightness in the same folder.
+pub fn set_brightness(brightness_file: &str, percent: u8) -> anyhow::Result<()> {
+    let brightness_file = std::path::Path::new(brightness_file);
+    let max_brightness_file = brightness_file.with_file_name("max_brightness");
+    let max_brightness: u32 = std::fs::read_to_string(&max_brightness_file)
+        .with_context(|| format!("Failed to read {}", max_brightness_file.display()))?
+        .trim()
+        .parse()
+        .with_context(|| format!("{} is not a number", max_brightness_file.display()))?;
+    let brightness = max_brightness * u32::from(percent.min(100)) / 100;
+    std::fs::write(brightness_file, brightness.to_string())
+        .with_context(|| format!("Failed to write {}", brightness_file.display()))
+}
+
+/// next_scroll_position returns the position in the string (in bytes) that scrolling text moves on to after the specified position.
+/// It scrolls to the next space, if it is reasonably soon, but not too soon, & starts again once the end of the string is nearly reached.
+pub fn next_scroll_position(string: &str, position: usize) -> usize {
+    let mut position = position;
+    let mut found_space = false;
+    for i in position + 6..position + 14 {
+        // check the index stays within bounds
+        if i + 1 < string.len() {
+            if string.as_bytes()[i] == b' ' {
+                position = i;
+                found_space = true;
                 break;
             }
+        } else {
+            break; // to avoid going beyond the end of the string
         }
-        if !found_space {
-            *position += 6;
-        }
+    }
+    if !found_space {
+        position += 6;
+    }
 
-        *position += 1; // Advance past the space (if the "for" loop found one), else advance 1 byte (perhaps not 1 character!)
-        if *position > string.len() - 10 {
-            // If we are almost at the end of the start again. Also ensure that position remains in bounds
-            *position = 0;
-        }
-        if (*position > 0) && !(&string.is_char_boundary(*position)) {
-            *position -= 1 // if we are not on a character boundary because we have a multi-byte character bring the pointer back one byte to be on a boundary
-        }
-        if (*position > 0) && !(&string.is_char_boundary(*position)) {
-            *position -= 1 // second attempt; a few characters use 3 bytes
+    position += 1; // Advance past the space (if the "for" loop found one), else advance 1 byte (perhaps not 1 character!)
+    if position > string.len().saturating_sub(10) {
+        // If we are almost at the end of the start again. Also ensure that position remains in bounds
+        position = 0;
+    }
+    if (position > 0) && !string.is_char_boundary(position) {
+        position -= 1 // if we are not on a character boundary because we have a multi-byte character bring the pointer back one byte to be on a boundary
+    }
+    if (position > 0) && !string.is_char_boundary(position) {
+        position -= 1 // second attempt; a few characters use 3 bytes
+    }
+    if (position > 0) && !string.is_char_boundary(position) {
+        position -= 1 // third attempt; a few characters use 4 bytes
+    }
+    position
+}
+
+/// scroll_position returns the position in the string (in bytes) after it has been scrolled the specified number of steps.
+/// As the scrolling starts again at the beginning once the end of the string is reached, only the steps since the last restart are taken.
+pub fn scroll_position(string: &str, steps: usize) -> usize {
+    let mut positions = vec![0];
+    loop {
+        let next_position = next_scroll_position(string, positions[positions.len() - 1]);
+        if next_position == 0 {
+            break;
         }
+        positions.push(next_position);
     }
+    positions[steps % positions.len()]
 }
 
 /*
@@ -318,6 +419,7 @@ impl Lc {
 "\x1b[LD" will enable the display, "\x1b[Ld" will disable it.
 "\x1b[LC" will turn the cursor on, "\x1b[Lc" will turn it off.
 "\x1b[LB" will enable blink. "\x1b[Lb" will disable it.
+"\x1b[L+" will turn the backlight on, "\x1b[L-" will turn it off.
 "\x1b[LL" will shift the display left. "\x1b[LR" will shift it right.
 "\x1b[Ll" will shift the cursor left. "\x1b[Lr" will shift it right.
 "\x1b[Lk" will erase the rest of the line.
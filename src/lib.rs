@@ -0,0 +1,31 @@
+//! The modules that make up the LCD screen driver for rradio. They are in a library, as well as the screen driver itself,
+//! so that the fake rradio server & the tests can use them too.
+
+pub mod clock;
+pub mod commands;
+pub mod compositor;
+pub mod config;
+pub mod control;
+pub mod driver;
+pub mod endpoint;
+pub mod error_list;
+pub mod errors;
+pub mod events;
+pub mod fake_rradio;
+pub mod get_local_ip_address;
+pub mod input;
+pub mod lcd;
+pub mod localisation;
+pub mod menu;
+pub mod metadata;
+pub mod pages;
+pub mod player_model;
+pub mod progress;
+pub mod recording;
+pub mod render;
+pub mod shutdown;
+pub mod splash;
+pub mod station_names;
+pub mod status_text;
+pub mod supervisor;
+pub mod system_info;
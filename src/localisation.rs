@@ -0,0 +1,691 @@
+use chrono::{DateTime, TimeZone};
+use serde::Deserialize;
+use std::sync::OnceLock;
+
+/// Locale is the language that messages are written to the screen in. It is set by `locale = "fr"` in the config file.
+#[derive(PartialEq, Eq, Debug, Clone, Copy, Default, Deserialize)]
+pub enum Locale {
+    #[default]
+    #[serde(rename = "en")]
+    English,
+    #[serde(rename = "fr")]
+    French,
+    #[serde(rename = "de")]
+    German,
+}
+
+pub const ALL_LOCALES: [Locale; 3] = [Locale::English, Locale::French, Locale::German];
+
+impl Locale {
+    /// index is the position of the translation for this locale in each entry of the catalogue
+    fn index(self) -> usize {
+        match self {
+            Locale::English => 0,
+            Locale::French => 1,
+            Locale::German => 2,
+        }
+    }
+
+    /// chrono_locale is the locale used by chrono for the names of months & days
+    fn chrono_locale(self) -> chrono::Locale {
+        match self {
+            Locale::English => chrono::Locale::en_GB,
+            Locale::French => chrono::Locale::fr_FR,
+            Locale::German => chrono::Locale::de_DE,
+        }
+    }
+}
+
+/// message_ids declares MessageId & ALL_MESSAGE_IDS from the one list of ids, so that the list of every message
+/// cannot leave any out
+macro_rules! message_ids {
+    ($($id:ident,)*) => {
+        /// MessageId identifies each message that can be written to the screen
+        #[derive(PartialEq, Eq, Debug, Clone, Copy)]
+        pub enum MessageId {
+            $($id,)*
+        }
+
+        /// every message, so that they can all be checked
+        pub const ALL_MESSAGE_IDS: &[MessageId] = &[$(MessageId::$id,)*];
+    };
+}
+
+message_ids! {
+    ExpectingVersion,
+    BadRRadioHeader,
+    NotUtf8,
+    NotRRadio,
+    VersionMismatch,
+    LcdDriverVersion,
+    RRadioVersion,
+    LcdVersion,
+    NoStation,
+    Station,
+    PlayingCd,
+    Usb,
+    File,
+    CdTrack,
+    CpuTemperature,
+    TemperatureAndStrength,
+    TemperatureAndTime,
+    Muted,
+    Volume,
+    VolumeLabel, // beside the volume in big digits
+    OtherErrors,
+    NoCd,
+    NoCdInfo,
+    CdTrayOpen,
+    CdTrayNotReady,
+    DataCd,
+    DataCdShort,
+    CdNotEnabled,
+    CdNotEnabledShort,
+    CdDriveError,
+    CdIoctlError,
+    CdStatusUnknown,
+    CdDriveStatusUnknown,
+    StationFolderError,
+    BadStationFile,
+    MountError,
+    UPnPError,
+    NoPlaylist,
+    InvalidTrack,
+    TagError,
+    PlaybackError,
+    EventPlayerStateChanged,
+    EventUndecodable,
+    EventConnectionClosed,
+    CommandFailed,
+    KeyEcho,
+    MenuTitle,
+    MenuStations,
+    MenuBacklight,
+    MenuBrightness,
+    MenuLayout,
+    MenuNetwork,
+    MenuBacklightOn,
+    MenuBacklightOff,
+    LayoutRotating,
+    LayoutNowPlaying,
+    LayoutClock,
+    MenuReconnect,
+    MenuRestart,
+    MenuShutDown,
+    MenuReboot,
+    MenuNo,
+    MenuYes,
+    ConfirmShutDown,
+    ConfirmReboot,
+    ShuttingDown,
+    Rebooting,
+    ShutdownElapsed,
+    ShutdownDryRun,
+    SafeToUnplug,
+    ShutdownFailed,
+    Reconnecting,
+    RetryIn,
+    StationNotFoundExplanation,
+    RemedyRecompileWithCd,
+    RemedyCheckCdDrive,
+    RemedyEjectAndRetry,
+    RemedyInsertAudioCd,
+    RemedyInsertCd,
+    RemedyCloseCdTray,
+    RemedyWaitAndRetry,
+    RemedyCheckStationsFolder,
+    RemedyTryAnotherChannel,
+    RemedyFixStationFile,
+    RemedyCheckUsb,
+    RemedyCheckNetwork,
+    RemedyWaitOrTryAnotherChannel,
+    PingLocal,
+    PingRemote,
+    PingLocalShort,
+    PingRemoteShort,
+    PingTime,
+    PingRxFail,
+    PingDestinationUnreachable,
+    PingNoReply,
+    PingTxFail,
+    PingDnsError,
+    BadUrl,
+    DateAndTime,
+    TimeOfDay,
+    PageWifiSsid,
+    PageWifiStrength,
+    PageNoPing,
+    PageLoadAverage,
+    PageUptime,
+    PageClockDate,
+    PageClockTime,
+}
+
+impl MessageId {
+    /// templates returns the English, French & German text of the message. {0}, {1} etc are replaced by the arguments.
+    /// DateAndTime & TimeOfDay are chrono format strings rather than templates.
+    fn templates(self) -> [&'static str; 3] {
+        match self {
+            // the spaces are intentional, so that the version starts on the second line
+            MessageId::ExpectingVersion => [
+                "Expecting version   {0} of rradio",
+                "Version attendue    {0} de rradio",
+                "Erwarte Version     {0} von rradio",
+            ],
+            MessageId::BadRRadioHeader => [
+                "Bad RRadio Header",
+                "En-tête rradio faux",
+                "rradio-Header falsch",
+            ],
+            MessageId::NotUtf8 => ["Not UTF-8", "Pas en UTF-8", "Kein UTF-8"],
+            MessageId::NotRRadio => ["Not RRadio", "Pas rradio", "Nicht rradio"],
+            MessageId::VersionMismatch => [
+                "Version mismatch",
+                "Versions différentes",
+                "Versionskonflikt",
+            ],
+            MessageId::LcdDriverVersion => {
+                ["LCD driver: {0}", "Pilote LCD: {0}", "LCD-Treiber: {0}"]
+            }
+            MessageId::RRadioVersion => ["rradio:     {0}", "rradio:     {0}", "rradio:      {0}"],
+            MessageId::LcdVersion => ["LCD Version {0}", "Version LCD {0}", "LCD-Version {0}"],
+            MessageId::NoStation => ["No station {0}", "Pas de station {0}", "Kein Sender {0}"],
+            MessageId::Station => ["Station {0}", "Station {0}", "Sender {0}"],
+            MessageId::PlayingCd => ["Playing CD", "Lecture CD", "CD läuft"],
+            MessageId::Usb => ["USB {0}", "USB {0}", "USB {0}"],
+            MessageId::File => ["File {0}", "Fichier {0}", "Datei {0}"],
+            MessageId::CdTrack => [
+                "CD track {0} of {1}",
+                "CD piste {0} sur {1}",
+                "CD Titel {0} von {1}",
+            ],
+            MessageId::CpuTemperature => ["CPU temp{0}C", "Temp CPU{0}C", "CPU-Temp{0}C"],
+            MessageId::TemperatureAndStrength => [
+                "CPU Temp {0}C WiFi{1}",
+                "Temp CPU {0}C WiFi{1}",
+                "CPU-Temp {0}C WLAN{1}",
+            ],
+            MessageId::TemperatureAndTime => [
+                "CPU temp {0} C {1}",
+                "Temp CPU {0} C {1}",
+                "CPU-Temp {0} C {1}",
+            ],
+            MessageId::Muted => ["Muted", "Muet", "Stumm"],
+            MessageId::Volume => ["Volume {0}", "Volume {0}", "Lautst. {0}"],
+            MessageId::VolumeLabel => ["Volume", "Volume", "Lautst."],
+            MessageId::OtherErrors => ["+{0} more", "+{0} autres", "+{0} weitere"],
+            MessageId::NoCd => ["No CD", "Pas de CD", "Keine CD"],
+            MessageId::NoCdInfo => [
+                "No CD information",
+                "Pas d'infos du CD",
+                "Keine CD-Information",
+            ],
+            MessageId::CdTrayOpen => ["CD tray open", "Tiroir CD ouvert", "CD-Lade offen"],
+            MessageId::CdTrayNotReady => [
+                "CD tray not ready",
+                "Tiroir CD pas prêt",
+                "CD-Lade nicht bereit",
+            ],
+            MessageId::DataCd => [
+                "This is a data CD so cannot play it. (Data type {0})",
+                "C'est un CD de données, illisible. (Type {0})",
+                "Daten-CD, kann nicht abgespielt werden. (Typ {0})",
+            ],
+            MessageId::DataCdShort => [
+                "Data CD (type {0})",
+                "CD données type {0}",
+                "Daten-CD (Typ {0})",
+            ],
+            MessageId::CdNotEnabled => [
+                "CD support is not enabled. You need to recompile",
+                "Le CD n'est pas activé. Il faut recompiler",
+                "CD-Unterstützung ist nicht aktiviert. Neu kompilieren",
+            ],
+            MessageId::CdNotEnabledShort => {
+                ["CD not enabled", "CD pas activé", "CD nicht aktiviert"]
+            }
+            MessageId::CdDriveError => [
+                "Cannot open CD drive",
+                "Erreur lecteur CD",
+                "CD-Laufwerk-Fehler",
+            ],
+            MessageId::CdIoctlError => [
+                "CD ioctl error {0}",
+                "Erreur ioctl CD {0}",
+                "CD-ioctl-Fehler {0}",
+            ],
+            MessageId::CdStatusUnknown => [
+                "Unknown disc status",
+                "État disque inconnu",
+                "Diskstatus unbekannt",
+            ],
+            MessageId::CdDriveStatusUnknown => [
+                "Unknown drive status",
+                "État lecteur inconnu",
+                "Laufwerk unbekannt",
+            ],
+            MessageId::StationFolderError => [
+                "Station folder error",
+                "Erreur dossier radio",
+                "Senderordner-Fehler",
+            ],
+            MessageId::BadStationFile => [
+                "Bad station file",
+                "Fichier station faux",
+                "Senderdatei falsch",
+            ],
+            MessageId::MountError => ["Cannot mount drive", "Montage impossible", "Mount-Fehler"],
+            MessageId::UPnPError => ["UPnP error", "Erreur UPnP", "UPnP-Fehler"],
+            MessageId::NoPlaylist => ["No playlist", "Pas de liste", "Keine Playlist"],
+            MessageId::InvalidTrack => [
+                "Bad track number",
+                "Numéro de piste faux",
+                "Falsche Titelnummer",
+            ],
+            MessageId::TagError => ["Bad track tags", "Tags de piste faux", "Fehlerhafte Tags"],
+            MessageId::PlaybackError => ["Playback error", "Erreur de lecture", "Wiedergabefehler"],
+            MessageId::EventPlayerStateChanged => [
+                "Player state changed",
+                "État du lecteur modifié",
+                "Playerstatus geändert",
+            ],
+            MessageId::EventUndecodable => [
+                "Unknown message from rradio",
+                "Message inconnu de rradio",
+                "Unbekannte Nachricht von rradio",
+            ],
+            MessageId::EventConnectionClosed => [
+                "rradio has stopped",
+                "rradio s'est arrêté",
+                "rradio wurde beendet",
+            ],
+            MessageId::CommandFailed => [
+                "Could not send the command to rradio",
+                "Commande non envoyée à rradio",
+                "Befehl nicht an rradio gesendet",
+            ],
+            MessageId::KeyEcho => ["Key {0}", "Touche {0}", "Taste {0}"],
+            MessageId::MenuTitle => ["Menu", "Menu", "Menü"],
+            MessageId::MenuStations => ["Stations", "Stations", "Sender"],
+            MessageId::MenuBacklight => ["Backlight", "Rétroéclairage", "Beleuchtung"],
+            MessageId::MenuBrightness => ["Brightness", "Luminosité", "Helligkeit"],
+            MessageId::MenuLayout => ["Layout", "Disposition", "Anzeige"],
+            MessageId::MenuNetwork => ["Network info", "Infos réseau", "Netzwerk-Info"],
+            MessageId::MenuBacklightOn => ["On", "Allumé", "An"],
+            MessageId::MenuBacklightOff => ["Off", "Éteint", "Aus"],
+            MessageId::LayoutRotating => ["Rotate pages", "Pages en rotation", "Seiten wechseln"],
+            MessageId::LayoutNowPlaying => {
+                ["Now playing only", "Lecture seulement", "Nur Wiedergabe"]
+            }
+            MessageId::LayoutClock => ["Clock only", "Horloge seulement", "Nur Uhr"],
+            MessageId::MenuReconnect => ["Reconnect", "Reconnecter", "Neu verbinden"],
+            MessageId::MenuRestart => ["Restart driver", "Relancer l'écran", "Anzeige neu starten"],
+            MessageId::MenuShutDown => ["Shut down", "Éteindre", "Herunterfahren"],
+            MessageId::MenuReboot => ["Reboot", "Redémarrer", "Neustart"],
+            MessageId::MenuNo => ["No", "Non", "Nein"],
+            MessageId::MenuYes => ["Yes", "Oui", "Ja"],
+            MessageId::ConfirmShutDown => ["Shut down?", "Éteindre ?", "Ausschalten?"],
+            MessageId::ConfirmReboot => ["Reboot?", "Redémarrer ?", "Neustarten?"],
+            MessageId::ShuttingDown => {
+                ["Shutting down...", "Arrêt en cours...", "Fährt herunter..."]
+            }
+            MessageId::Rebooting => ["Rebooting...", "Redémarrage...", "Neustart läuft..."],
+            MessageId::ShutdownElapsed => ["{0}s so far", "{0}s écoulées", "{0}s vergangen"],
+            MessageId::ShutdownDryRun => ["Dry run", "Simulation", "Testlauf"],
+            MessageId::SafeToUnplug => {
+                ["Safe to unplug", "Débranchement sûr", "Stecker ziehen: OK"]
+            }
+            MessageId::ShutdownFailed => [
+                "Could not shut down",
+                "Arrêt impossible",
+                "Beenden fehlgeschl.",
+            ],
+            MessageId::Reconnecting => ["Reconnecting...", "Reconnexion...", "Neu verbinden..."],
+            MessageId::RetryIn => [
+                "Retry {0} in {1}s",
+                "Essai {0} dans {1}s",
+                "Versuch {0} in {1}s",
+            ],
+            MessageId::StationNotFoundExplanation => {
+                ["Not in {0}", "Absente de {0}", "Nicht in {0}"]
+            }
+            MessageId::RemedyRecompileWithCd => [
+                "Rebuild rradio with the CD feature",
+                "Recompiler rradio avec le CD",
+                "rradio mit CD-Funktion neu bauen",
+            ],
+            MessageId::RemedyCheckCdDrive => [
+                "Check the CD drive is plugged in",
+                "Vérifier que le lecteur CD est branché",
+                "Ist das CD-Laufwerk angeschlossen?",
+            ],
+            MessageId::RemedyEjectAndRetry => [
+                "Eject the CD & try again",
+                "Éjecter le CD et réessayer",
+                "CD auswerfen & erneut versuchen",
+            ],
+            MessageId::RemedyInsertAudioCd => [
+                "Insert an audio CD",
+                "Insérer un CD audio",
+                "Audio-CD einlegen",
+            ],
+            MessageId::RemedyInsertCd => ["Insert a CD", "Insérer un CD", "CD einlegen"],
+            MessageId::RemedyCloseCdTray => [
+                "Close the CD tray",
+                "Fermer le tiroir CD",
+                "CD-Lade schließen",
+            ],
+            MessageId::RemedyWaitAndRetry => [
+                "Wait a moment & try again",
+                "Attendre un peu et réessayer",
+                "Kurz warten & erneut versuchen",
+            ],
+            MessageId::RemedyCheckStationsFolder => [
+                "Check the stations folder exists",
+                "Vérifier le dossier des stations",
+                "Gibt es den Senderordner?",
+            ],
+            MessageId::RemedyTryAnotherChannel => [
+                "Try another channel",
+                "Essayer une autre station",
+                "Anderen Sender wählen",
+            ],
+            MessageId::RemedyFixStationFile => [
+                "Correct the station file",
+                "Corriger le fichier de la station",
+                "Senderdatei korrigieren",
+            ],
+            MessageId::RemedyCheckUsb => [
+                "Check the USB stick is inserted",
+                "Vérifier que la clé USB est branchée",
+                "Ist der USB-Stick eingesteckt?",
+            ],
+            MessageId::RemedyCheckNetwork => [
+                "Check the network & the server",
+                "Vérifier le réseau et le serveur",
+                "Netzwerk & Server prüfen",
+            ],
+            MessageId::RemedyWaitOrTryAnotherChannel => [
+                "Wait, or try another channel",
+                "Attendre ou essayer une autre station",
+                "Warten oder anderen Sender wählen",
+            ],
+            MessageId::PingLocal => ["Local", "Local", "Lokal"],
+            MessageId::PingRemote => ["Remote", "Distant", "Fern"],
+            MessageId::PingLocalShort => ["Loc", "Loc", "Lok"],
+            MessageId::PingRemoteShort => ["Rem", "Dis", "Fer"],
+            MessageId::PingTime => ["{0} ping {1}ms", "Ping {0} {1}ms", "{0} Ping {1}ms"],
+            MessageId::PingRxFail => [
+                "{0} ping Rx fail",
+                "Ping {0} échec Rx",
+                "{0} Ping Rx-Fehler",
+            ],
+            MessageId::PingDestinationUnreachable => [
+                "{0} Dest Unreachable",
+                "{0} Dest inaccessible",
+                "{0} Ziel unerreichbar",
+            ],
+            MessageId::PingNoReply => [
+                "{0} ping: No reply",
+                "Ping {0} sans réponse",
+                "{0} Ping: Keine Antwort",
+            ],
+            MessageId::PingTxFail => [
+                "{0} ping: Tx Fail",
+                "Ping {0} échec Tx",
+                "{0} Ping Tx-Fehler",
+            ],
+            MessageId::PingDnsError => [
+                "{0} ping DNS error",
+                "Ping {0} erreur DNS",
+                "{0} Ping DNS-Fehler",
+            ],
+            MessageId::BadUrl => ["Bad URL", "URL invalide", "Ungültige URL"],
+            // French month abbreviations can be 5 characters long eg "févr.", so the year is left out to make it fit
+            MessageId::DateAndTime => [
+                "  %d %b %y %H:%M:%S",
+                "  %d %b %H:%M:%S",
+                "  %d %b %y %H:%M:%S",
+            ],
+            MessageId::TimeOfDay => ["%H:%M", "%H:%M", "%H:%M"],
+            MessageId::PageWifiSsid => ["WiFi {0}", "WiFi {0}", "WLAN {0}"],
+            MessageId::PageWifiStrength => ["Signal {0}dBm", "Signal {0}dBm", "Signal {0}dBm"],
+            MessageId::PageNoPing => ["No ping yet", "Pas encore de ping", "Noch kein Ping"],
+            MessageId::PageLoadAverage => ["Load {0}", "Ch. {0}", "Last {0}"],
+            MessageId::PageUptime => ["Up {0}", "Actif {0}", "Läuft {0}"],
+            MessageId::PageClockDate => ["%a %d %b %Y", "%a %d %b %Y", "%a %d %b %Y"],
+            MessageId::PageClockTime => ["%H:%M:%S", "%H:%M:%S", "%H:%M:%S"],
+        }
+    }
+
+    /// width returns the number of characters the message must fit in, or None if it does not matter because
+    /// the message is one of several variants, & a shorter variant is used if it does not fit.
+    /// The arguments are typical values used when checking the width.
+    fn width_and_sample_arguments(self) -> (Option<usize>, &'static [&'static str]) {
+        const LINE: usize = crate::lcd::NUM_CHARACTERS_PER_LINE;
+        const LINE1_DATA: usize = crate::lcd::LINE1_DATA_CHAR_COUNT_USIZE;
+        match self {
+            MessageId::ExpectingVersion => (Some(LINE * 2), &["0.38.0"]),
+            MessageId::BadRRadioHeader
+            | MessageId::NotUtf8
+            | MessageId::NotRRadio
+            | MessageId::VersionMismatch
+            | MessageId::NoCd
+            | MessageId::NoCdInfo
+            | MessageId::CdTrayOpen
+            | MessageId::CdTrayNotReady
+            | MessageId::CdNotEnabledShort
+            | MessageId::CdDriveError
+            | MessageId::CdStatusUnknown
+            | MessageId::CdDriveStatusUnknown
+            | MessageId::StationFolderError
+            | MessageId::BadStationFile
+            | MessageId::MountError
+            | MessageId::UPnPError
+            | MessageId::NoPlaylist
+            | MessageId::InvalidTrack
+            | MessageId::TagError
+            | MessageId::PlaybackError => (Some(LINE), &[]),
+            MessageId::CdIoctlError => (Some(LINE), &["-22"]),
+            MessageId::Reconnecting => (Some(LINE), &[]),
+            MessageId::RetryIn => (Some(LINE), &["99", "30"]),
+            MessageId::RemedyRecompileWithCd
+            | MessageId::RemedyCheckCdDrive
+            | MessageId::RemedyEjectAndRetry
+            | MessageId::RemedyInsertAudioCd
+            | MessageId::RemedyInsertCd
+            | MessageId::RemedyCloseCdTray
+            | MessageId::RemedyWaitAndRetry
+            | MessageId::RemedyCheckStationsFolder
+            | MessageId::RemedyTryAnotherChannel
+            | MessageId::RemedyFixStationFile
+            | MessageId::RemedyCheckUsb
+            | MessageId::RemedyCheckNetwork
+            | MessageId::RemedyWaitOrTryAnotherChannel
+            | MessageId::EventPlayerStateChanged
+            | MessageId::EventUndecodable
+            | MessageId::EventConnectionClosed
+            | MessageId::CommandFailed => (Some(LINE * 2), &[]),
+            MessageId::KeyEcho => (Some(LINE * 2), &["CHANNELDOWN"]),
+            // the titles of the menus share the top line with the position in the menu
+            MessageId::MenuTitle
+            | MessageId::MenuStations
+            | MessageId::MenuBacklight
+            | MessageId::MenuBrightness
+            | MessageId::MenuLayout
+            | MessageId::ConfirmShutDown
+            | MessageId::ConfirmReboot => (Some(crate::menu::TITLE_WIDTH), &[]),
+            MessageId::MenuNetwork
+            | MessageId::MenuBacklightOn
+            | MessageId::MenuBacklightOff
+            | MessageId::LayoutRotating
+            | MessageId::LayoutNowPlaying
+            | MessageId::LayoutClock
+            | MessageId::MenuReconnect
+            | MessageId::MenuRestart
+            | MessageId::MenuShutDown
+            | MessageId::MenuReboot
+            | MessageId::MenuNo
+            | MessageId::MenuYes => (Some(crate::menu::ENTRY_WIDTH), &[]),
+            MessageId::ShuttingDown
+            | MessageId::Rebooting
+            | MessageId::ShutdownDryRun
+            | MessageId::SafeToUnplug
+            | MessageId::ShutdownFailed => (Some(LINE), &[]),
+            MessageId::ShutdownElapsed => (Some(LINE), &["9999"]),
+            MessageId::LcdDriverVersion | MessageId::RRadioVersion | MessageId::LcdVersion => {
+                (Some(LINE), &["0.38.0"])
+            }
+            MessageId::NoStation => (Some(LINE), &["99"]),
+            MessageId::Station | MessageId::Usb | MessageId::File => (Some(LINE1_DATA), &["99"]),
+            MessageId::PlayingCd => (Some(LINE1_DATA), &[]),
+            MessageId::CdTrack => (Some(LINE), &["12", "15"]),
+            MessageId::CpuTemperature => (Some(LINE1_DATA), &[" 45"]),
+            MessageId::TemperatureAndStrength => (Some(LINE), &["45", "-52"]),
+            MessageId::TemperatureAndTime => (Some(LINE), &["45", "12:34"]),
+            MessageId::Muted | MessageId::VolumeLabel => (Some(crate::lcd::VOLUME_CHAR_COUNT), &[]),
+            MessageId::DataCdShort => (Some(LINE), &["XA21"]),
+            MessageId::OtherErrors => (Some(LINE / 2), &["9"]),
+            MessageId::BadUrl => (Some(LINE1_DATA), &[]),
+            MessageId::PingLocalShort | MessageId::PingRemoteShort => (Some(3), &[]),
+            MessageId::DateAndTime => (Some(LINE), &[]),
+            MessageId::TimeOfDay => (Some(5), &[]),
+            MessageId::PageWifiSsid => (Some(LINE), &["HomeNetwork"]),
+            MessageId::PageWifiStrength => (Some(LINE), &["-52"]),
+            MessageId::PageNoPing | MessageId::PageClockDate => (Some(LINE), &[]),
+            MessageId::PageLoadAverage => (Some(LINE), &["0.12 0.30 0.25"]),
+            MessageId::PageUptime => (Some(LINE), &["123d 04:12"]),
+            MessageId::PageClockTime => (Some(8), &[]),
+            MessageId::Volume
+            | MessageId::DataCd
+            | MessageId::CdNotEnabled
+            | MessageId::StationNotFoundExplanation
+            | MessageId::PingLocal
+            | MessageId::PingRemote
+            | MessageId::PingTime
+            | MessageId::PingRxFail
+            | MessageId::PingDestinationUnreachable
+            | MessageId::PingNoReply
+            | MessageId::PingTxFail
+            | MessageId::PingDnsError => (None, &[]),
+        }
+    }
+
+    /// is_date_format is true if the message is a chrono format string rather than a template
+    fn is_date_format(self) -> bool {
+        matches!(
+            self,
+            MessageId::DateAndTime
+                | MessageId::TimeOfDay
+                | MessageId::PageClockDate
+                | MessageId::PageClockTime
+        )
+    }
+}
+
+static LOCALE: OnceLock<Locale> = OnceLock::new();
+
+/// set_locale sets the language used for all the messages. It can only be set once, when the program starts.
+pub fn set_locale(locale: Locale) {
+    if LOCALE.set(locale).is_err() {
+        println!("The locale has already been set, so ignoring {locale:?}");
+    }
+}
+
+/// locale returns the language being used, which is English unless set_locale has been called
+pub fn locale() -> Locale {
+    LOCALE.get().copied().unwrap_or_default()
+}
+
+/// fill_in replaces {0}, {1} etc in the template with the arguments
+fn fill_in(template: &str, arguments: &[&dyn std::fmt::Display]) -> String {
+    let mut result = template.to_string();
+    for (index, argument) in arguments.iter().enumerate() {
+        result = result.replace(&format!("{{{index}}}"), &argument.to_string());
+    }
+    result
+}
+
+/// text_in returns the message in the specified language with the arguments filled in
+pub fn text_in(locale: Locale, id: MessageId, arguments: &[&dyn std::fmt::Display]) -> String {
+    fill_in(id.templates()[locale.index()], arguments)
+}
+
+/// text returns the message in the language being used with the arguments filled in
+pub fn text(id: MessageId, arguments: &[&dyn std::fmt::Display]) -> String {
+    text_in(locale(), id, arguments)
+}
+
+/// format_date_time_in formats the date &/or time using the format & the names of months & days of the specified language
+pub fn format_date_time_in<Tz>(locale: Locale, id: MessageId, date_time: &DateTime<Tz>) -> String
+where
+    Tz: TimeZone,
+    Tz::Offset: std::fmt::Display,
+{
+    date_time
+        .format_localized(id.templates()[locale.index()], locale.chrono_locale())
+        .to_string()
+}
+
+/// format_date_time formats the date &/or time using the language being used
+pub fn format_date_time<Tz>(id: MessageId, date_time: &DateTime<Tz>) -> String
+where
+    Tz: TimeZone,
+    Tz::Offset: std::fmt::Display,
+{
+    format_date_time_in(locale(), id, date_time)
+}
+
+/// check_widths checks that every message in every language fits in the space it is written to, & returns a description
+/// of each one that does not. Dates are checked using the longest names of the months & days.
+pub fn check_widths() -> Vec<String> {
+    let mut problems = Vec::new();
+    for locale in ALL_LOCALES {
+        for &id in ALL_MESSAGE_IDS {
+            let (width, sample_arguments) = id.width_and_sample_arguments();
+            let Some(width) = width else { continue };
+            let samples: Vec<String> = if id.is_date_format() {
+                (1..=12)
+                    .flat_map(|month| (21..=27).map(move |day| (month, day))) // every month & every day of the week
+                    .filter_map(|(month, day)| {
+                        chrono::Utc
+                            .with_ymd_and_hms(2026, month, day, 23, 59, 59)
+                            .single()
+                    })
+                    .map(|date_time| format_date_time_in(locale, id, &date_time))
+                    .collect()
+            } else {
+                let arguments: Vec<&dyn std::fmt::Display> = sample_arguments
+                    .iter()
+                    .map(|argument| argument as &dyn std::fmt::Display)
+                    .collect();
+                vec![text_in(locale, id, &arguments)]
+            };
+            if let Some(too_long) = samples.iter().find(|sample| sample.chars().count() > width) {
+                problems.push(format!(
+                    "{locale:?} {id:?}: \"{too_long}\" is {} characters long, but only {width} fit",
+                    too_long.chars().count()
+                ));
+            }
+        }
+    }
+    problems
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_translation_fits() {
+        assert_eq!(check_widths(), Vec::<String>::new());
+    }
+
+    #[test]
+    fn arguments_are_filled_in() {
+        assert_eq!(
+            text_in(Locale::German, MessageId::CdTrack, &[&3, &12]),
+            "CD Titel 3 von 12"
+        );
+    }
+}
@@ -0,0 +1,491 @@
+//! The menu, for changing settings & for diagnostics. It takes over the whole screen while it is open:
+//! the title is on line 1 & the entries scroll through lines 2 to 4, with the cursor blinking on the chosen one.
+//! It only knows about navigation, eg next & select, so it does not matter which controls drive it.
+
+use serde::Deserialize;
+use std::time::Duration;
+use tokio::time::Instant;
+
+use crate::input::Action;
+use crate::lcd::{Frame, LineNum, NUM_CHARACTERS_PER_LINE};
+use crate::localisation::{text, MessageId};
+use crate::pages::{LayoutProfile, Page};
+use crate::shutdown::PowerOff;
+
+/// the number of lines used for the entries, ie all but the title
+const ENTRY_LINES: usize = 3;
+/// the room for the position in the menu, eg " 12/15", at the end of the title line
+const POSITION_WIDTH: usize = 6;
+/// the room for the title of a menu
+pub const TITLE_WIDTH: usize = NUM_CHARACTERS_PER_LINE - POSITION_WIDTH;
+/// the room for an entry, which follows the column for the cursor
+pub const ENTRY_WIDTH: usize = NUM_CHARACTERS_PER_LINE - 1;
+
+/// the brightnesses, as percentages, that can be chosen from the menu
+const BRIGHTNESSES: [u8; 4] = [25, 50, 75, 100];
+
+/// MenuConfig is the configuration of the menu, eg
+/// ```toml
+/// [menu]
+/// timeout_secs = 20
+/// brightness_file = "/sys/class/backlight/lcd/brightness"
+/// ```
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct MenuConfig {
+    /// how long the menu stays open after the last button press, before the screen goes back to what is playing
+    pub timeout_secs: u64,
+    /// the file that sets the brightness of a backlight driven by PWM, if there is one; otherwise the brightness is not in the menu
+    pub brightness_file: Option<String>,
+}
+
+impl Default for MenuConfig {
+    fn default() -> Self {
+        MenuConfig {
+            timeout_secs: 30,
+            brightness_file: None,
+        }
+    }
+}
+
+/// Navigation is what the controls do while the menu is open
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum Navigation {
+    Previous, // up the screen
+    Next,     // down the screen
+    Select,
+    Back,
+}
+
+impl Navigation {
+    /// from_action returns the navigation done by an action while the menu is open. Turning the volume knob clockwise,
+    /// or pressing station down, moves down the menu, as the channels are listed in order.
+    pub fn from_action(action: Action) -> Option<Navigation> {
+        match action {
+            Action::StationUp | Action::VolumeDown => Some(Navigation::Previous),
+            Action::StationDown | Action::VolumeUp => Some(Navigation::Next),
+            Action::NextPage => Some(Navigation::Select),
+            Action::Menu | Action::Mute => Some(Navigation::Back),
+            Action::Digit(_) | Action::ShutDown | Action::Reboot => None,
+        }
+    }
+}
+
+/// MenuChoice is what the driver is asked to do when an entry is chosen
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub enum MenuChoice {
+    SetChannel(String),
+    Backlight(bool), // true for on
+    Brightness(u8),  // as a percentage
+    Layout(LayoutProfile),
+    Reconnect,
+    RestartDriver,
+    PowerOff(PowerOff), // once it has been confirmed
+}
+
+impl MenuChoice {
+    /// closes_menu is true if the menu is closed once the choice has been made. The menu stays open for the backlight
+    /// & the brightness, so that the effect can be seen & changed again.
+    pub fn closes_menu(&self) -> bool {
+        !matches!(self, MenuChoice::Backlight(_) | MenuChoice::Brightness(_))
+    }
+}
+
+/// MenuEntry is one line of a menu
+#[derive(Debug, Clone)]
+pub struct MenuEntry {
+    label: String,
+    kind: EntryKind,
+}
+
+#[derive(Debug, Clone)]
+enum EntryKind {
+    Submenu(String, Vec<MenuEntry>), // the title & the entries
+    Show(Page),                      // shows the page until Back or Select is pressed
+    Choice(MenuChoice),
+    Back, // eg "No" when asked to confirm
+}
+
+impl MenuEntry {
+    pub fn submenu(label: String, entries: Vec<MenuEntry>) -> Self {
+        MenuEntry {
+            kind: EntryKind::Submenu(label.clone(), entries),
+            label,
+        }
+    }
+
+    /// confirmation returns an entry that asks whether the choice is really wanted before it is made
+    pub fn confirmation(label: String, choice: MenuChoice) -> Self {
+        let question = match choice {
+            MenuChoice::PowerOff(power_off) => text(power_off.question(), &[]),
+            _ => format!("{}?", label),
+        };
+        MenuEntry {
+            label,
+            kind: EntryKind::Submenu(question, confirmation_entries(choice)),
+        }
+    }
+
+    pub fn show(label: String, page: Page) -> Self {
+        MenuEntry {
+            label,
+            kind: EntryKind::Show(page),
+        }
+    }
+
+    pub fn choice(label: String, choice: MenuChoice) -> Self {
+        MenuEntry {
+            label,
+            kind: EntryKind::Choice(choice),
+        }
+    }
+}
+
+/// confirmation_entries returns the answers to a question asking whether the choice is really wanted,
+/// with No first, so that pressing Select by mistake does nothing
+fn confirmation_entries(choice: MenuChoice) -> Vec<MenuEntry> {
+    vec![
+        MenuEntry {
+            label: text(MessageId::MenuNo, &[]),
+            kind: EntryKind::Back,
+        },
+        MenuEntry::choice(text(MessageId::MenuYes, &[]), choice),
+    ]
+}
+
+/// main_menu returns the entries of the top level of the menu. The stations are the channels & their names;
+/// if there are none, all the channels are listed.
+pub fn main_menu(stations: Vec<(String, String)>, can_set_brightness: bool) -> Vec<MenuEntry> {
+    let station_entries = if stations.is_empty() {
+        (0..100)
+            .map(|channel| {
+                let channel = format!("{:02}", channel);
+                MenuEntry::choice(channel.clone(), MenuChoice::SetChannel(channel))
+            })
+            .collect()
+    } else {
+        stations
+            .into_iter()
+            .map(|(channel, name)| {
+                MenuEntry::choice(
+                    format!("{} {}", channel, name),
+                    MenuChoice::SetChannel(channel),
+                )
+            })
+            .collect()
+    };
+    let label = |id| text(id, &[]);
+    let mut entries = vec![
+        MenuEntry::submenu(label(MessageId::MenuStations), station_entries),
+        MenuEntry::show(label(MessageId::MenuNetwork), Page::Network),
+        MenuEntry::submenu(
+            label(MessageId::MenuBacklight),
+            vec![
+                MenuEntry::choice(
+                    label(MessageId::MenuBacklightOn),
+                    MenuChoice::Backlight(true),
+                ),
+                MenuEntry::choice(
+                    label(MessageId::MenuBacklightOff),
+                    MenuChoice::Backlight(false),
+                ),
+            ],
+        ),
+    ];
+    if can_set_brightness {
+        entries.push(MenuEntry::submenu(
+            label(MessageId::MenuBrightness),
+            BRIGHTNESSES
+                .iter()
+                .map(|&percent| {
+                    MenuEntry::choice(format!("{}%", percent), MenuChoice::Brightness(percent))
+                })
+                .collect(),
+        ));
+    }
+    entries.extend(vec![
+        MenuEntry::submenu(
+            label(MessageId::MenuLayout),
+            vec![
+                MenuEntry::choice(
+                    label(MessageId::LayoutRotating),
+                    MenuChoice::Layout(LayoutProfile::Rotating),
+                ),
+                MenuEntry::choice(
+                    label(MessageId::LayoutNowPlaying),
+                    MenuChoice::Layout(LayoutProfile::NowPlayingOnly),
+                ),
+                MenuEntry::choice(
+                    label(MessageId::LayoutClock),
+                    MenuChoice::Layout(LayoutProfile::ClockOnly),
+                ),
+            ],
+        ),
+        MenuEntry::choice(label(MessageId::MenuReconnect), MenuChoice::Reconnect),
+        MenuEntry::choice(label(MessageId::MenuRestart), MenuChoice::RestartDriver),
+        MenuEntry::confirmation(
+            label(MessageId::MenuReboot),
+            MenuChoice::PowerOff(PowerOff::Reboot),
+        ),
+        MenuEntry::confirmation(
+            label(MessageId::MenuShutDown),
+            MenuChoice::PowerOff(PowerOff::ShutDown),
+        ),
+    ]);
+    entries
+}
+
+/// MenuOutcome is the result of navigating the menu
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub enum MenuOutcome {
+    Open,   // the menu is still open, eg the cursor has moved
+    Closed, // Back was pressed at the top level
+    Chosen(MenuChoice),
+}
+
+/// Level is one level of the menu, ie the top level or a submenu
+#[derive(Debug)]
+struct Level {
+    title: String,
+    entries: Vec<MenuEntry>,
+    cursor: usize,      // the index of the chosen entry
+    first_shown: usize, // the index of the entry on line 2
+}
+
+impl Level {
+    fn new(title: String, entries: Vec<MenuEntry>) -> Self {
+        Level {
+            title,
+            entries,
+            cursor: 0,
+            first_shown: 0,
+        }
+    }
+
+    /// move_cursor moves the cursor by the offset, going round from the end to the start & vice versa,
+    /// & scrolls the entries so that the cursor can be seen
+    fn move_cursor(&mut self, offset: isize) {
+        if self.entries.is_empty() {
+            return;
+        }
+        self.cursor =
+            (self.cursor as isize + offset).rem_euclid(self.entries.len() as isize) as usize;
+        if self.cursor < self.first_shown {
+            self.first_shown = self.cursor;
+        } else if self.cursor >= self.first_shown + ENTRY_LINES {
+            self.first_shown = self.cursor + 1 - ENTRY_LINES;
+        }
+    }
+}
+
+/// Menu is the state of the open menu. It is closed by dropping it.
+#[derive(Debug)]
+pub struct Menu {
+    levels: Vec<Level>, // the top level first, then each submenu that has been opened
+    showing: Option<Page>,
+    last_used: Instant,
+    timeout: Duration,
+}
+
+impl Menu {
+    /// open returns the menu, open at the top level with the cursor on the first entry
+    pub fn open(entries: Vec<MenuEntry>, config: &MenuConfig, now: Instant) -> Self {
+        Menu {
+            levels: vec![Level::new(text(MessageId::MenuTitle, &[]), entries)],
+            showing: None,
+            last_used: now,
+            timeout: Duration::from_secs(config.timeout_secs),
+        }
+    }
+
+    /// confirm returns the menu open at the question whether the computer is really to be shut down or rebooted,
+    /// eg when a button for shutting down is pressed
+    pub fn confirm(power_off: PowerOff, config: &MenuConfig, now: Instant) -> Self {
+        Menu {
+            levels: vec![Level::new(
+                text(power_off.question(), &[]),
+                confirmation_entries(MenuChoice::PowerOff(power_off)),
+            )],
+            showing: None,
+            last_used: now,
+            timeout: Duration::from_secs(config.timeout_secs),
+        }
+    }
+
+    /// navigate moves around the menu, & returns the choice if an entry is chosen
+    pub fn navigate(&mut self, navigation: Navigation, now: Instant) -> MenuOutcome {
+        self.last_used = now;
+        if self.showing.take().is_some() {
+            return MenuOutcome::Open; // any button goes back to the menu from a page
+        }
+        let level = self
+            .levels
+            .last_mut()
+            .expect("the menu always has a top level");
+        match navigation {
+            Navigation::Previous => level.move_cursor(-1),
+            Navigation::Next => level.move_cursor(1),
+            Navigation::Back => {
+                self.levels.pop();
+                if self.levels.is_empty() {
+                    return MenuOutcome::Closed;
+                }
+            }
+            Navigation::Select => {
+                let Some(entry) = level.entries.get(level.cursor) else {
+                    return MenuOutcome::Open;
+                };
+                match &entry.kind {
+                    EntryKind::Submenu(title, entries) => {
+                        let submenu = Level::new(title.clone(), entries.clone());
+                        self.levels.push(submenu);
+                    }
+                    EntryKind::Show(page) => self.showing = Some(*page),
+                    EntryKind::Choice(choice) => return MenuOutcome::Chosen(choice.clone()),
+                    EntryKind::Back => return self.navigate(Navigation::Back, now),
+                }
+            }
+        }
+        MenuOutcome::Open
+    }
+
+    /// has_timed_out is true once the menu has not been used for a while
+    pub fn has_timed_out(&self, now: Instant) -> bool {
+        now.saturating_duration_since(self.last_used) >= self.timeout
+    }
+
+    /// showing returns the page chosen from the menu, eg the network information, if a page is being shown instead of the menu
+    pub fn showing(&self) -> Option<Page> {
+        self.showing
+    }
+
+    /// frame returns the menu as it is drawn on the screen: the title & the position on line 1,
+    /// & the entries, with a marker next to the chosen one, on the other lines
+    pub fn frame(&self) -> Frame {
+        let level = self.level();
+        let mut frame = Frame::new();
+        frame.write(LineNum::Line1, 0, &level.title);
+        let position = format!("{}/{}", level.cursor + 1, level.entries.len());
+        frame.write(
+            LineNum::Line1,
+            NUM_CHARACTERS_PER_LINE - position.len(),
+            &position,
+        );
+        let entry_lines = &LineNum::ALL[1..];
+        for (line_number, (index, entry)) in entry_lines
+            .iter()
+            .zip(level.entries.iter().enumerate().skip(level.first_shown))
+        {
+            let marker = if index == level.cursor { ">" } else { " " };
+            frame.write(*line_number, 0, marker);
+            frame.write(*line_number, 1, &entry.label);
+        }
+        frame
+    }
+
+    /// cursor returns where the cursor blinks, ie on the marker next to the chosen entry
+    pub fn cursor(&self) -> (LineNum, usize) {
+        let level = self.level();
+        (LineNum::ALL[1 + level.cursor - level.first_shown], 0)
+    }
+
+    fn level(&self) -> &Level {
+        self.levels.last().expect("the menu always has a top level")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn menu(now: Instant) -> Menu {
+        let stations = vec![
+            ("01".to_string(), "Jazz FM".to_string()),
+            ("02".to_string(), "Radio 3".to_string()),
+        ];
+        Menu::open(main_menu(stations, false), &MenuConfig::default(), now)
+    }
+
+    #[test]
+    fn a_station_can_be_chosen() {
+        let now = Instant::now();
+        let mut menu = menu(now);
+        assert_eq!(menu.frame().line(LineNum::Line1), "Menu             1/8");
+        assert_eq!(menu.frame().line(LineNum::Line2).trim_end(), ">Stations");
+        assert_eq!(menu.navigate(Navigation::Select, now), MenuOutcome::Open);
+        assert_eq!(menu.frame().line(LineNum::Line1), "Stations         1/2");
+        assert_eq!(menu.navigate(Navigation::Next, now), MenuOutcome::Open);
+        assert_eq!(menu.frame().line(LineNum::Line3).trim_end(), ">02 Radio 3");
+        assert_eq!(menu.cursor(), (LineNum::Line3, 0));
+        assert_eq!(
+            menu.navigate(Navigation::Select, now),
+            MenuOutcome::Chosen(MenuChoice::SetChannel("02".to_string()))
+        );
+    }
+
+    #[test]
+    fn the_entries_scroll_to_keep_the_cursor_on_the_screen() {
+        let now = Instant::now();
+        let mut menu = menu(now);
+        menu.navigate(Navigation::Previous, now); // goes round to the last entry
+        let frame = menu.frame();
+        assert_eq!(frame.line(LineNum::Line1), "Menu             8/8");
+        assert_eq!(frame.line(LineNum::Line2).trim_end(), " Restart driver");
+        assert_eq!(frame.line(LineNum::Line4).trim_end(), ">Shut down");
+        assert_eq!(menu.cursor(), (LineNum::Line4, 0));
+
+        menu.navigate(Navigation::Next, now); // goes round to the first entry
+        assert_eq!(menu.frame().line(LineNum::Line2).trim_end(), ">Stations");
+    }
+
+    #[test]
+    fn back_goes_up_a_level_then_closes_the_menu() {
+        let now = Instant::now();
+        let mut menu = menu(now);
+        menu.navigate(Navigation::Next, now);
+        menu.navigate(Navigation::Select, now);
+        assert_eq!(menu.showing(), Some(Page::Network));
+        menu.navigate(Navigation::Back, now);
+        assert_eq!(menu.showing(), None);
+        assert_eq!(
+            menu.frame().line(LineNum::Line3).trim_end(),
+            ">Network info"
+        );
+
+        menu.navigate(Navigation::Next, now);
+        menu.navigate(Navigation::Select, now);
+        assert_eq!(menu.frame().line(LineNum::Line1), "Backlight        1/2");
+        assert_eq!(menu.navigate(Navigation::Back, now), MenuOutcome::Open);
+        assert_eq!(menu.navigate(Navigation::Back, now), MenuOutcome::Closed);
+    }
+
+    #[test]
+    fn shutting_down_has_to_be_confirmed() {
+        let now = Instant::now();
+        let mut menu = menu(now);
+        menu.navigate(Navigation::Previous, now);
+        menu.navigate(Navigation::Select, now);
+        assert_eq!(menu.frame().line(LineNum::Line1), "Shut down?       1/2");
+        assert_eq!(menu.frame().line(LineNum::Line2).trim_end(), ">No");
+        assert_eq!(menu.navigate(Navigation::Select, now), MenuOutcome::Open);
+        assert_eq!(menu.frame().line(LineNum::Line4).trim_end(), ">Shut down"); // back where it was
+
+        let mut menu = Menu::confirm(PowerOff::Reboot, &MenuConfig::default(), now);
+        assert_eq!(menu.frame().line(LineNum::Line1), "Reboot?          1/2");
+        menu.navigate(Navigation::Next, now);
+        assert_eq!(
+            menu.navigate(Navigation::Select, now),
+            MenuOutcome::Chosen(MenuChoice::PowerOff(PowerOff::Reboot))
+        );
+    }
+
+    #[test]
+    fn the_menu_times_out() {
+        let now = Instant::now();
+        let mut menu = menu(now);
+        let later = now + Duration::from_secs(20);
+        menu.navigate(Navigation::Next, later);
+        assert!(!menu.has_timed_out(later + Duration::from_secs(29)));
+        assert!(menu.has_timed_out(later + Duration::from_secs(30)));
+    }
+}
@@ -0,0 +1,283 @@
+use regex::Regex;
+
+use crate::config::{MetadataConfig, RewriteRule};
+
+/// TrackMetadata holds the text of the tags that describe what is playing
+#[derive(PartialEq, Debug, Clone, Default)]
+pub struct TrackMetadata {
+    pub title: String,
+    pub artist: String,
+    pub album: String,
+    pub organisation: String,
+}
+
+impl From<&rradio_messages::TrackTags> for TrackMetadata {
+    fn from(tags: &rradio_messages::TrackTags) -> Self {
+        let as_string = |tag: &Option<rradio_messages::ArcStr>| {
+            tag.as_ref().map(|tag| tag.to_string()).unwrap_or_default()
+        };
+        TrackMetadata {
+            title: as_string(&tags.title),
+            artist: as_string(&tags.artist),
+            album: as_string(&tags.album),
+            organisation: as_string(&tags.organisation),
+        }
+    }
+}
+
+/// RewriteRules is an ordered list of compiled rewrite rules
+#[derive(Debug, Default)]
+pub struct RewriteRules(Vec<(Regex, String)>);
+
+impl RewriteRules {
+    /// new compiles the rules. Any rule that is not a valid regular expression is reported & skipped.
+    pub fn new(rules: &[RewriteRule]) -> Self {
+        RewriteRules(
+            rules
+                .iter()
+                .filter_map(|rule| match Regex::new(&rule.pattern) {
+                    Ok(regex) => Some((regex, rule.replacement.clone())),
+                    Err(error) => {
+                        println!("Ignoring rewrite rule {:?} as {error}", rule.pattern);
+                        None
+                    }
+                })
+                .collect(),
+        )
+    }
+
+    /// apply applies each of the rules in turn to the text
+    pub fn apply(&self, text: &str) -> String {
+        self.0
+            .iter()
+            .fold(text.to_string(), |text, (regex, replacement)| {
+                regex.replace_all(&text, replacement.as_str()).into_owned()
+            })
+    }
+}
+
+/// MetadataRules tidies up the tags sent by internet streams, CDs etc so that they can be shown consistently
+#[derive(Debug, Default)]
+pub struct MetadataRules {
+    rewrite: RewriteRules,
+    discard: Vec<Regex>,
+}
+
+impl MetadataRules {
+    pub fn new(config: &MetadataConfig) -> Self {
+        MetadataRules {
+            rewrite: RewriteRules::new(&config.rewrite),
+            discard: config
+                .discard
+                .iter()
+                .filter_map(|pattern| match Regex::new(pattern) {
+                    Ok(regex) => Some(regex),
+                    Err(error) => {
+                        println!("Ignoring discard pattern {pattern:?} as {error}");
+                        None
+                    }
+                })
+                .collect(),
+        }
+    }
+
+    /// clean rewrites one tag, squashes repeated white space, & returns an empty string
+    /// if the tag is junk, eg "Unknown artist" or an advert
+    fn clean(&self, tag: &str) -> String {
+        let tidied = self
+            .rewrite
+            .apply(tag)
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" ");
+        if tidied.to_lowercase().starts_with("unknown")
+            || self.discard.iter().any(|regex| regex.is_match(&tidied))
+        {
+            String::new()
+        } else {
+            tidied
+        }
+    }
+
+    /// normalise cleans the tags, splits "Artist - Title" titles as sent by internet streams into the artist & the title,
+    /// & removes text that merely repeats the name of the station
+    pub fn normalise(&self, station_title: &str, tags: &TrackMetadata) -> TrackMetadata {
+        let mut title = self.clean(&tags.title);
+        let mut artist = self.clean(&tags.artist);
+        let mut album = self.clean(&tags.album);
+        let organisation = self.clean(&tags.organisation);
+
+        if let Some((artist_in_title, title_only)) = title.clone().split_once(" - ") {
+            // internet streams usually send everything in the title as "Artist - Title"
+            if artist.is_empty() || same_text(&artist, artist_in_title.trim()) {
+                artist = artist_in_title.trim().to_string();
+                title = title_only.trim().to_string();
+            }
+        }
+
+        let is_station_name =
+            |text: &str| same_text(text, station_title) || same_text(text, &organisation);
+        if is_station_name(&artist) {
+            artist.clear();
+        }
+        if is_station_name(&title) {
+            title.clear();
+        }
+        if is_station_name(&album) || same_text(&album, &title) || same_text(&album, &artist) {
+            album.clear();
+        }
+
+        TrackMetadata {
+            title,
+            artist,
+            album,
+            organisation,
+        }
+    }
+}
+
+/// same_text is true if the two strings are the same ignoring case, & are not empty
+fn same_text(first: &str, second: &str) -> bool {
+    !first.is_empty() && first.to_lowercase() == second.to_lowercase()
+}
+
+/// assemble_line2 concatonates organisation/station_title, artist & album adding a "/" as a separator, skipping any that are empty.
+/// If pause_before_playing > 0 appends " wait" followed by the value of pause_before_playing. Organisation is used if not empty, else station_title is used.
+/// Returns the result of the concatonation.
+pub fn assemble_line2(
+    organisation: &str,
+    station_title: &str,
+    artist: &str,
+    album: &str,
+    pause_before_playing: u64,
+) -> String {
+    let title_shown = if !organisation.is_empty() {
+        organisation
+    } else {
+        station_title
+    };
+
+    let station_and_artist_and_album = [title_shown, artist, album]
+        .iter()
+        .filter(|text| !text.is_empty())
+        .copied()
+        .collect::<Vec<_>>()
+        .join(" / ");
+
+    if pause_before_playing > 0 {
+        format!(
+            "{} wait{}",
+            station_and_artist_and_album, pause_before_playing
+        )
+    } else {
+        station_and_artist_and_album
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// a table of tags as actually received from stations, CDs & USB sticks: the station title,
+    /// the title, artist, album & organisation as received, then the title, artist & album expected
+    const SAMPLES: &[(&str, [&str; 4], [&str; 3])] = &[
+        (
+            "Jazz FM",
+            ["Miles Davis - So What", "", "", "Jazz FM"],
+            ["So What", "Miles Davis", ""],
+        ),
+        (
+            "Radio Paradise",
+            ["StreamTitle='Adele - Hello';StreamUrl='';", "", "", ""],
+            ["Hello", "Adele", ""],
+        ),
+        (
+            "Radio Paradise",
+            ["StreamTitle='Talk Talk - It's My Life';", "", "", ""],
+            ["It's My Life", "Talk Talk", ""],
+        ),
+        (
+            "BBC Radio 4",
+            ["BBC Radio 4 - The Archers", "", "", "BBC Radio 4"],
+            ["The Archers", "", ""],
+        ),
+        (
+            "Classic FM",
+            ["Classic FM", "Classic FM", "", "Classic FM"],
+            ["", "", ""],
+        ),
+        ("Absolute 80s", ["Advertisement", "", "", ""], ["", "", ""]),
+        (
+            "Absolute 80s",
+            ["www.absoluteradio.co.uk", "", "", ""],
+            ["", "", ""],
+        ),
+        (
+            "CD",
+            ["Bohemian Rhapsody", "Queen", "A Night at the Opera", ""],
+            ["Bohemian Rhapsody", "Queen", "A Night at the Opera"],
+        ),
+        (
+            "Planet Rock",
+            ["Queen - Bohemian Rhapsody", "Queen", "", ""],
+            ["Bohemian Rhapsody", "Queen", ""],
+        ),
+        (
+            "Planet Rock",
+            ["AC/DC - Back In Black - Remastered", "", "", ""],
+            ["Back In Black - Remastered", "AC/DC", ""],
+        ),
+        (
+            "USB",
+            ["Track 01", "Unknown Artist", "Unknown Album", ""],
+            ["Track 01", "", ""],
+        ),
+        (
+            "Radio Swiss Jazz",
+            ["  Ella  Fitzgerald -  Summertime ", "", "", ""],
+            ["Summertime", "Ella Fitzgerald", ""],
+        ),
+        (
+            "FIP",
+            ["Jean-Michel Jarre - Oxygène 4", "", "", ""],
+            ["Oxygène 4", "Jean-Michel Jarre", ""],
+        ),
+        (
+            "Samba",
+            ["Help!", "The Beatles", "Help!", ""],
+            ["Help!", "The Beatles", ""],
+        ),
+    ];
+
+    #[test]
+    fn real_world_tag_samples() {
+        let rules = MetadataRules::new(&MetadataConfig::default());
+        for (station_title, [title, artist, album, organisation], expected) in SAMPLES {
+            let tags = TrackMetadata {
+                title: title.to_string(),
+                artist: artist.to_string(),
+                album: album.to_string(),
+                organisation: organisation.to_string(),
+            };
+            let normalised = rules.normalise(station_title, &tags);
+            assert_eq!(
+                [
+                    normalised.title.as_str(),
+                    normalised.artist.as_str(),
+                    normalised.album.as_str()
+                ],
+                *expected,
+                "for the tags {tags:?} of station {station_title}"
+            );
+        }
+    }
+
+    #[test]
+    fn line2_uses_the_organisation_in_preference_to_the_station_title() {
+        assert_eq!(
+            assemble_line2("BBC", "Radio 4", "Queen", "", 0),
+            "BBC / Queen"
+        );
+        assert_eq!(assemble_line2("", "Radio 4", "", "", 3), "Radio 4 wait3");
+    }
+}
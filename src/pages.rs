@@ -0,0 +1,300 @@
+use chrono::{DateTime, FixedOffset};
+use serde::Deserialize;
+use std::time::Duration;
+use tokio::time::Instant;
+
+use crate::lcd::NUM_CHARACTERS_PER_LINE;
+use crate::localisation::{format_date_time, text, MessageId};
+use crate::status_text::{PingStatus, StatusText};
+use crate::system_info::SystemReadings;
+
+/// Page is one of the pages of information that the display can rotate through
+#[derive(PartialEq, Eq, Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Page {
+    NowPlaying, // the normal layout showing the station, the track etc
+    Network,    // IP address, Wi-Fi SSID & signal strength & the latest ping
+    System,     // CPU temperature, load average & uptime
+    Clock,
+}
+
+/// PageConfig is the configuration of one page, eg
+/// ```toml
+/// [[pages]]
+/// page = "clock"
+/// dwell_secs = 5
+/// always = false
+/// ```
+#[derive(Debug, Clone, Deserialize)]
+pub struct PageConfig {
+    pub page: Page,
+    /// how long the page is shown before going on to the next page
+    #[serde(default = "default_dwell_secs")]
+    pub dwell_secs: u64,
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    /// if true the page is shown even when the player is playing, otherwise it is only shown when the player is idle
+    #[serde(default)]
+    pub always: bool,
+}
+
+fn default_dwell_secs() -> u64 {
+    5
+}
+
+fn default_true() -> bool {
+    true
+}
+
+/// default_pages are the pages used if none are given in the config file
+pub fn default_pages() -> Vec<PageConfig> {
+    let page_config = |page, dwell_secs, always| PageConfig {
+        page,
+        dwell_secs,
+        enabled: true,
+        always,
+    };
+    vec![
+        page_config(Page::NowPlaying, 10, true),
+        page_config(Page::Network, 5, false),
+        page_config(Page::System, 5, false),
+        page_config(Page::Clock, 5, false),
+    ]
+}
+
+/// LayoutProfile is which pages are shown, as chosen from the menu
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum LayoutProfile {
+    Rotating,       // the pages in the config file
+    NowPlayingOnly, // what is playing, without the other pages
+    ClockOnly,      // the clock all the time, eg for a radio by the bed
+}
+
+/// PageRotation keeps track of which page is being shown & when it is time to go on to the next one
+#[derive(Debug)]
+pub struct PageRotation {
+    configured_pages: Vec<PageConfig>, // the enabled pages in the config file
+    pages: Vec<PageConfig>,            // the pages of the current layout profile
+    current: usize,
+    shown_since: Instant,
+    chosen: bool, // true if the current page was chosen with a button, so it is shown for its dwell time even while playing
+}
+
+impl PageRotation {
+    pub fn new(pages: &[PageConfig], now: Instant) -> Self {
+        let configured_pages: Vec<PageConfig> =
+            pages.iter().filter(|page| page.enabled).cloned().collect();
+        PageRotation {
+            pages: configured_pages.clone(),
+            configured_pages,
+            current: 0,
+            shown_since: now,
+            chosen: false,
+        }
+    }
+
+    /// set_profile changes the pages that are shown, starting again from the first
+    pub fn set_profile(&mut self, profile: LayoutProfile, now: Instant) {
+        self.pages = match profile {
+            LayoutProfile::Rotating => self.configured_pages.clone(),
+            LayoutProfile::NowPlayingOnly => Vec::new(), // with no pages, the now playing page is shown
+            LayoutProfile::ClockOnly => vec![PageConfig {
+                page: Page::Clock,
+                dwell_secs: default_dwell_secs(),
+                enabled: true,
+                always: true,
+            }],
+        };
+        self.current = 0;
+        self.shown_since = now;
+        self.chosen = false;
+    }
+
+    /// show_next shows the next page straight away, eg when a button is pressed
+    pub fn show_next(&mut self, now: Instant) {
+        if !self.pages.is_empty() {
+            self.current = (self.current + 1) % self.pages.len();
+            self.shown_since = now;
+            self.chosen = true;
+        }
+    }
+
+    /// current_page returns the page to show now. When the player is playing only the pages
+    /// marked "always" are rotated through; if there are none the now playing page is shown.
+    pub fn current_page(&mut self, now: Instant, player_is_idle: bool) -> Page {
+        let can_show = |page: &PageConfig| player_is_idle || page.always;
+
+        let Some(current) = self.pages.get(self.current) else {
+            return Page::NowPlaying;
+        };
+        let dwell_time = Duration::from_secs(current.dwell_secs);
+
+        if !(self.chosen || can_show(current))
+            || now.saturating_duration_since(self.shown_since) >= dwell_time
+        {
+            self.chosen = false;
+            // go on to the next page that can be shown, which may be the current page if no others can be shown
+            match (1..=self.pages.len())
+                .map(|offset| (self.current + offset) % self.pages.len())
+                .find(|&index| can_show(&self.pages[index]))
+            {
+                Some(next) => {
+                    self.current = next;
+                    self.shown_since = now;
+                }
+                None => return Page::NowPlaying,
+            }
+        }
+        self.pages[self.current].page
+    }
+}
+
+/// PageInfo is the information shown on the pages other than the now playing page. The readings are the latest ones taken
+/// by system_info::take_readings, as some of them, eg the SSID, take a while to get.
+#[derive(Debug, Clone, Copy)]
+pub struct PageInfo<'a> {
+    pub system: &'a SystemReadings,
+    pub ping_status: Option<PingStatus>,
+    pub now: DateTime<FixedOffset>,
+}
+
+/// page_lines returns the four lines of the specified page, or None for the now playing page, which has its own layout
+pub fn page_lines(page: Page, info: &PageInfo) -> Option<[String; 4]> {
+    match page {
+        Page::NowPlaying => None,
+        Page::Network => Some([
+            info.system.ip_address.clone(),
+            text(MessageId::PageWifiSsid, &[&info.system.wifi_ssid]),
+            text(MessageId::PageWifiStrength, &[&info.system.wifi_strength]),
+            match info.ping_status {
+                Some(ping_status) => ping_status.fit(NUM_CHARACTERS_PER_LINE),
+                None => text(MessageId::PageNoPing, &[]),
+            },
+        ]),
+        Page::System => Some([
+            text(
+                MessageId::CpuTemperature,
+                &[&format!("{:>3}", info.system.cpu_temperature)],
+            ),
+            text(MessageId::PageLoadAverage, &[&info.system.load_average]),
+            text(
+                MessageId::PageUptime,
+                &[&info
+                    .system
+                    .uptime
+                    .map(crate::system_info::format_uptime)
+                    .unwrap_or_default()],
+            ),
+            String::new(),
+        ]),
+        Page::Clock => Some([
+            String::new(),
+            format!(
+                "{:^width$}",
+                format_date_time(MessageId::PageClockDate, &info.now),
+                width = NUM_CHARACTERS_PER_LINE
+            ),
+            format!(
+                "{:^width$}",
+                format_date_time(MessageId::PageClockTime, &info.now),
+                width = NUM_CHARACTERS_PER_LINE
+            ),
+            String::new(),
+        ]),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn only_pages_marked_always_are_shown_while_playing() {
+        let start = Instant::now();
+        let mut rotation = PageRotation::new(&default_pages(), start);
+        assert_eq!(rotation.current_page(start, false), Page::NowPlaying);
+        assert_eq!(
+            rotation.current_page(start + Duration::from_secs(60), false),
+            Page::NowPlaying
+        );
+
+        // once the player is idle, each page is shown for its dwell time in turn
+        let idle = start + Duration::from_secs(60);
+        assert_eq!(rotation.current_page(idle, true), Page::NowPlaying);
+        assert_eq!(
+            rotation.current_page(idle + Duration::from_secs(10), true),
+            Page::Network
+        );
+        assert_eq!(
+            rotation.current_page(idle + Duration::from_secs(14), true),
+            Page::Network
+        );
+        assert_eq!(
+            rotation.current_page(idle + Duration::from_secs(15), true),
+            Page::System
+        );
+
+        // & as soon as it starts playing again it goes back to the now playing page
+        assert_eq!(
+            rotation.current_page(idle + Duration::from_secs(16), false),
+            Page::NowPlaying
+        );
+
+        // unless a page is chosen with a button, in which case it is shown for its dwell time
+        let chosen = idle + Duration::from_secs(20);
+        rotation.show_next(chosen);
+        assert_eq!(rotation.current_page(chosen, false), Page::Network);
+        assert_eq!(
+            rotation.current_page(chosen + Duration::from_secs(5), false),
+            Page::NowPlaying
+        );
+    }
+
+    #[test]
+    fn the_system_page_shows_the_latest_readings() {
+        let system = SystemReadings {
+            cpu_temperature: 45,
+            load_average: "0.12 0.30 0.25".to_string(),
+            uptime: Some(Duration::from_secs(((3 * 24 + 4) * 60 + 12) * 60)),
+            ..SystemReadings::default()
+        };
+        let info = PageInfo {
+            system: &system,
+            ping_status: None,
+            now: chrono::FixedOffset::east_opt(0)
+                .unwrap()
+                .timestamp_opt(0, 0)
+                .unwrap(),
+        };
+        let lines = page_lines(Page::System, &info).unwrap();
+        assert_eq!(lines[0], "CPU temp 45C");
+        assert_eq!(lines[1], "Load 0.12 0.30 0.25");
+        assert_eq!(lines[2], "Up 3d 04:12");
+        assert_eq!(page_lines(Page::NowPlaying, &info), None);
+    }
+
+    #[test]
+    fn the_layout_profile_chooses_the_pages() {
+        let start = Instant::now();
+        let mut rotation = PageRotation::new(&default_pages(), start);
+        rotation.set_profile(LayoutProfile::ClockOnly, start);
+        assert_eq!(rotation.current_page(start, false), Page::Clock);
+        assert_eq!(
+            rotation.current_page(start + Duration::from_secs(60), true),
+            Page::Clock
+        );
+
+        rotation.set_profile(LayoutProfile::NowPlayingOnly, start);
+        assert_eq!(
+            rotation.current_page(start + Duration::from_secs(60), true),
+            Page::NowPlaying
+        );
+
+        rotation.set_profile(LayoutProfile::Rotating, start);
+        assert_eq!(
+            rotation.current_page(start + Duration::from_secs(10), true),
+            Page::Network
+        );
+    }
+}
@@ -0,0 +1,433 @@
+use rradio_messages::{
+    CurrentStation, PingTimes, PipelineState, PlayerStateDiff, StationError, StationType,
+};
+use std::time::Duration;
+use tokio::time::Instant;
+
+use crate::error_list::{ErrorList, ErrorState};
+use crate::errors::{describe_error, describe_station_error};
+use crate::metadata::{MetadataRules, TrackMetadata};
+use crate::station_names::StationNames;
+use crate::status_text::PingStatus;
+use crate::system_info::SystemReadings;
+
+/// PlayerModel is everything that rradio has told us about what it is playing, tidied up ready to be shown on the screen.
+/// It is only changed by apply, so the screen can be drawn from it at any time by render::render.
+#[derive(Debug)]
+pub struct PlayerModel {
+    metadata_rules: MetadataRules,
+    station_names: StationNames,
+    /// true once the first station has started playing; until then the start up screen is shown
+    pub started_up: bool,
+    pub pipe_line_state: PipelineState,
+    pub is_muted: bool, // in practice always false
+    pub volume: i32,    // negative until rradio has told us the volume
+    pub station_type: StationType,
+    pub channel: String, //normally in the range "00" to "99"
+    pub station_title: String,
+    /// when the station last changed, or None if rradio has not sent a station
+    pub station_change_time: Option<Instant>,
+    pub number_of_tracks: usize, // number of tracks on a USB stick or CD or channels on a URL, excluding notifications
+    pub current_track_index: usize, // index of which track on the CD or USB stick
+    /// the tidied up tags of the current track, or None if there are none
+    pub track: Option<TrackMetadata>,
+    /// when the text that can scroll (the title & line 2) last changed, so that scrolling starts from the beginning
+    pub scroll_start_time: Option<Instant>,
+    pub pause_before_playing: u64, // in seconds
+    pub buffering: Option<u8>,     // how full the gstreamer buffer is as a percentage
+    pub track_duration: Option<Duration>,
+    pub track_position: Option<Duration>,
+    /// when rradio sent the track position, so that the position can be worked out between the times rradio sends it
+    pub track_position_time: Option<Instant>,
+    pub ping_status: Option<PingStatus>,
+    /// after pinging the remote server rradio stops pinging, so the ping time & the CPU temperature are shown alternately
+    pub show_temperature_instead_of_gateway_ping: bool,
+    alternate_ping_and_temperature: bool,
+    pub errors: ErrorList,
+    /// true if the same missing channel was asked for twice running, when the version information is shown
+    pub channel_not_found_twice: bool,
+    last_channel_not_found: String,
+    pub system: SystemReadings,
+}
+
+impl Default for PlayerModel {
+    fn default() -> Self {
+        Self::new(MetadataRules::default(), StationNames::default())
+    }
+}
+
+impl PlayerModel {
+    pub fn new(metadata_rules: MetadataRules, station_names: StationNames) -> Self {
+        PlayerModel {
+            metadata_rules,
+            station_names,
+            started_up: false,
+            pipe_line_state: PipelineState::Null,
+            is_muted: false,
+            volume: -1,
+            station_type: StationType::CD, //not true but we have to initiialise it to something
+            channel: String::new(),
+            station_title: String::new(),
+            station_change_time: None,
+            number_of_tracks: 0,
+            current_track_index: 0,
+            track: None,
+            scroll_start_time: None,
+            pause_before_playing: 0,
+            buffering: None,
+            track_duration: None,
+            track_position: None,
+            track_position_time: None,
+            ping_status: None,
+            show_temperature_instead_of_gateway_ping: false,
+            alternate_ping_and_temperature: false,
+            errors: ErrorList::new(),
+            channel_not_found_twice: false,
+            last_channel_not_found: String::new(), // an invalid value intentionally
+            system: SystemReadings::default(),
+        }
+    }
+
+    /// is_idle is true if the player has started up but is not playing, & there are no errors to show,
+    /// so that the screen can rotate through the other pages without hiding the start up screen or an error
+    pub fn is_idle(&self, now: Instant) -> bool {
+        self.started_up
+            && self.pipe_line_state != PipelineState::Playing
+            && self.errors.active(now).next().is_none()
+    }
+
+    /// track_position_at returns how far through the track the player is at the time specified, working it out from
+    /// the last position rradio sent, as rradio does not send it every second. It is None if the track does not have a position,
+    /// eg because it is a stream rather than a CD, a USB stick or a file.
+    pub fn track_position_at(&self, now: Instant) -> Option<Duration> {
+        let position = self.track_position?;
+        if self.pipe_line_state != PipelineState::Playing {
+            return Some(position);
+        }
+        let since_sent = self
+            .track_position_time
+            .map_or(Duration::ZERO, |time| now.saturating_duration_since(time));
+        let position = position + since_sent;
+        Some(match self.track_duration {
+            Some(duration) => position.min(duration), // in case rradio is late moving on to the next track
+            None => position,
+        })
+    }
+
+    /// station_names returns the names of the stations from the config file
+    pub fn station_names(&self) -> &StationNames {
+        &self.station_names
+    }
+
+    /// reset forgets everything rradio has told us, ready for the complete state that rradio sends
+    /// when we reconnect to it. The rules from the config file & the latest system readings are kept.
+    pub fn reset(&mut self) {
+        let metadata_rules = std::mem::take(&mut self.metadata_rules);
+        let station_names = std::mem::take(&mut self.station_names);
+        let system = std::mem::take(&mut self.system);
+        *self = PlayerModel {
+            system,
+            ..PlayerModel::new(metadata_rules, station_names)
+        };
+    }
+
+    /// apply updates the model with the differences sent by rradio, which arrived at the time specified.
+    /// Fields of the diff that are None have not changed.
+    pub fn apply(&mut self, diff: PlayerStateDiff, now: Instant) {
+        // the station must be changed first, as changing it forgets what was known about the previous station's track,
+        // & tidying up the tags needs the station title
+        if let Some(current_station) = diff.current_station {
+            self.change_station(current_station, now);
+        }
+        if let Some(pipe_line_state) = diff.pipeline_state {
+            // the position moves on while playing, so it is worked out at the moment it stops or starts moving
+            self.track_position = self.track_position_at(now);
+            self.track_position_time = self.track_position.map(|_| now);
+            self.pipe_line_state = pipe_line_state;
+            if pipe_line_state == PipelineState::Playing {
+                self.errors.clear(ErrorState::GStreamerError); // it is playing again, so it must have recovered
+            }
+        }
+        if let Some(is_muted) = diff.is_muted {
+            self.is_muted = is_muted;
+        }
+        if let Some(volume) = diff.volume {
+            self.volume = volume;
+        }
+        if let Some(buffering) = diff.buffering {
+            self.buffering = Some(buffering);
+        }
+        if let Some(pause_before_playing) = diff.pause_before_playing {
+            self.pause_before_playing = pause_before_playing.map_or(0, |pause| pause.as_secs());
+        }
+        // do NOT set current_track_index to zero when the station changes. rradio sends it whenever the station is changed.
+        if let Some(current_track_index) = diff.current_track_index {
+            self.current_track_index = current_track_index;
+        }
+        if let Some(track_duration) = diff.track_duration {
+            self.track_duration = track_duration;
+        }
+        if let Some(track_position) = diff.track_position {
+            self.track_position = track_position;
+            self.track_position_time = Some(now);
+        }
+        if let Some(ping_times) = diff.ping_times {
+            self.alternate_ping_and_temperature = !self.alternate_ping_and_temperature;
+            self.show_temperature_instead_of_gateway_ping = self.alternate_ping_and_temperature
+                && matches!(ping_times, PingTimes::FinishedPingingRemote { .. });
+            self.ping_status = PingStatus::latest(&ping_times);
+        }
+        match diff.latest_error {
+            Some(Some(latest_error)) => {
+                self.errors.report(describe_error(&latest_error.error), now)
+            }
+            Some(None) => self.errors.clear(ErrorState::GStreamerError), // rradio has recovered
+            None => {}
+        }
+        match diff.current_track_tags {
+            Some(Some(track_tags)) => {
+                let track = self.metadata_rules.normalise(
+                    &self.station_title,
+                    &self
+                        .station_names
+                        .clean_tags(&TrackMetadata::from(&track_tags)),
+                );
+                if self.track.as_ref() != Some(&track) {
+                    self.track = Some(track);
+                    self.scroll_start_time = Some(now);
+                }
+            }
+            Some(None) => self.track = None,
+            None => {}
+        }
+    }
+
+    /// change_station updates the model when rradio has changed station, or failed to change station
+    fn change_station(&mut self, current_station: CurrentStation, now: Instant) {
+        self.station_change_time = Some(now);
+        self.scroll_start_time = Some(now);
+        self.track = None;
+        self.buffering = None;
+        self.track_duration = None; // otherwise the progress through the previous station's track is shown until rradio sends the new one
+        self.track_position = None;
+        self.track_position_time = None;
+        self.errors.clear_station_errors(); // any errors from the previous station no longer apply
+
+        match current_station {
+            CurrentStation::NoStation => {
+                self.channel = String::new();
+                self.station_title = String::new();
+            }
+            CurrentStation::FailedToPlayStation { error } => {
+                if let StationError::StationNotFound { index, .. } = &error {
+                    self.channel = index.to_string();
+                    self.station_title = String::new();
+                    self.channel_not_found_twice = self.last_channel_not_found == self.channel;
+                    self.last_channel_not_found = self.channel.clone();
+                }
+                self.errors.report(describe_station_error(&error), now);
+            }
+            CurrentStation::PlayingStation {
+                index,       // this is the channel number in the range 00 to 99
+                source_type, // eg CD
+                title,       // title of the station eg Tradcan
+                tracks,      // an array of all the tracks
+            } => {
+                self.started_up = true;
+                self.station_type = source_type;
+                self.channel = match index {
+                    Some(index) => index.to_string(),
+                    None => "??".to_string(), // somebody has selected a podcast, so there is no channel number
+                };
+                // tidy up the station name, eg remove the string "QUIET: " before the start of it as it is obvious,
+                // or use the name given to the channel in the config file
+                self.station_title = self
+                    .station_names
+                    .display_title(&self.channel, title.as_deref().unwrap_or_default());
+                self.number_of_tracks = tracks.as_ref().map_or(0, |tracks| {
+                    tracks
+                        .iter() // we iterate through the tracks, excluding those that are merely notifications, & count them
+                        .filter(|track| !track.is_notification) // a "notification" is a a sound, eg a ding, to say something has occurred as different from something to listen to
+                        .count()
+                });
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rradio_messages::{CdError, StationIndex, TrackTags};
+
+    fn playing_station(index: &str, title: &str) -> PlayerStateDiff {
+        PlayerStateDiff {
+            current_station: Some(CurrentStation::PlayingStation {
+                index: Some(StationIndex::new(index.to_string())),
+                source_type: StationType::UrlList,
+                title: Some(title.into()),
+                tracks: None,
+            }),
+            ..PlayerStateDiff::default()
+        }
+    }
+
+    fn station_not_found(index: &str) -> PlayerStateDiff {
+        PlayerStateDiff {
+            current_station: Some(CurrentStation::FailedToPlayStation {
+                error: StationError::StationNotFound {
+                    index: StationIndex::new(index.to_string()),
+                    directory: "/stations".into(),
+                },
+            }),
+            ..PlayerStateDiff::default()
+        }
+    }
+
+    #[test]
+    fn changing_station_clears_the_previous_tags() {
+        let now = Instant::now();
+        let mut model = PlayerModel::default();
+        model.apply(playing_station("07", "Jazz FM"), now);
+        model.apply(
+            PlayerStateDiff {
+                current_track_tags: Some(Some(TrackTags {
+                    title: Some("Miles Davis - So What".into()),
+                    ..TrackTags::default()
+                })),
+                ..PlayerStateDiff::default()
+            },
+            now,
+        );
+        assert!(model.started_up);
+        assert_eq!(model.errors.state(now), ErrorState::NoError);
+        assert_eq!(
+            model.track.as_ref().map(|track| track.title.as_str()),
+            Some("So What")
+        );
+
+        model.track_duration = Some(Duration::from_secs(200));
+        model.track_position = Some(Duration::from_secs(12));
+        model.apply(playing_station("08", "Classic FM"), now);
+        assert_eq!(model.channel, "08");
+        assert_eq!(model.station_title, "Classic FM");
+        assert_eq!(model.track, None);
+        assert_eq!(model.track_position, None);
+    }
+
+    #[test]
+    fn the_position_stops_moving_when_paused() {
+        let now = Instant::now();
+        let mut model = PlayerModel::default();
+        model.apply(
+            PlayerStateDiff {
+                pipeline_state: Some(PipelineState::Playing),
+                track_duration: Some(Some(Duration::from_secs(200))),
+                track_position: Some(Some(Duration::from_secs(10))),
+                ..PlayerStateDiff::default()
+            },
+            now,
+        );
+        assert_eq!(
+            model.track_position_at(now + Duration::from_secs(3)),
+            Some(Duration::from_secs(13))
+        );
+        model.apply(
+            PlayerStateDiff {
+                pipeline_state: Some(PipelineState::Paused),
+                ..PlayerStateDiff::default()
+            },
+            now + Duration::from_secs(3),
+        );
+        assert_eq!(
+            model.track_position_at(now + Duration::from_secs(60)),
+            Some(Duration::from_secs(13))
+        );
+        assert_eq!(
+            model.track_position_at(now + Duration::from_secs(600)),
+            Some(Duration::from_secs(13))
+        );
+    }
+
+    #[test]
+    fn a_missing_channel_asked_for_twice_is_noticed() {
+        let now = Instant::now();
+        let mut model = PlayerModel::default();
+        model.apply(station_not_found("42"), now);
+        assert_eq!(model.errors.state(now), ErrorState::NoStation);
+        assert!(!model.channel_not_found_twice);
+        model.apply(station_not_found("42"), now);
+        assert!(model.channel_not_found_twice);
+    }
+
+    #[test]
+    fn a_cd_error_is_cleared_by_playing_a_station() {
+        let now = Instant::now();
+        let mut model = PlayerModel::default();
+        model.apply(
+            PlayerStateDiff {
+                current_station: Some(CurrentStation::FailedToPlayStation {
+                    error: StationError::CdError(CdError::NoCd),
+                }),
+                ..PlayerStateDiff::default()
+            },
+            now,
+        );
+        let cd_error = model.errors.most_important(now).unwrap();
+        assert_eq!(cd_error.description.state, ErrorState::CdError);
+        assert_eq!(cd_error.description.headline, "No CD");
+        model.apply(playing_station("01", "Radio 1"), now);
+        assert_eq!(model.errors.state(now), ErrorState::NoError);
+        assert_eq!(model.errors.most_important(now), None);
+    }
+
+    #[test]
+    fn reset_forgets_the_old_state_but_keeps_the_rules() {
+        let now = Instant::now();
+        let mut model = PlayerModel::new(
+            MetadataRules::default(),
+            StationNames::new(&crate::config::StationNamesConfig {
+                display_names: vec![("07".to_string(), "Jazz".to_string())]
+                    .into_iter()
+                    .collect(),
+                ..crate::config::StationNamesConfig::default()
+            }),
+        );
+        model.apply(station_not_found("42"), now);
+        model.reset();
+        assert!(!model.started_up);
+        assert_eq!(model.channel, "");
+        assert_eq!(model.errors.state(now), ErrorState::NoError);
+
+        model.apply(playing_station("07", "Jazz FM 102.2"), now);
+        assert_eq!(model.station_title, "Jazz");
+    }
+
+    #[test]
+    fn a_gstreamer_error_is_cleared_when_rradio_recovers() {
+        let now = Instant::now();
+        let mut model = PlayerModel::default();
+        model.apply(playing_station("01", "Radio 1"), now);
+        model.apply(
+            PlayerStateDiff {
+                latest_error: Some(Some(rradio_messages::LatestError {
+                    timestamp: 0,
+                    error: rradio_messages::Error::PipelineError(
+                        "Internal data stream error".into(),
+                    ),
+                })),
+                ..PlayerStateDiff::default()
+            },
+            now,
+        );
+        assert_eq!(model.errors.state(now), ErrorState::GStreamerError);
+        model.apply(
+            PlayerStateDiff {
+                latest_error: Some(None),
+                ..PlayerStateDiff::default()
+            },
+            now,
+        );
+        assert_eq!(model.errors.state(now), ErrorState::NoError);
+    }
+}
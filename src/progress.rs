@@ -0,0 +1,291 @@
+//! How far through the track the player is, for the sources that have tracks with a length, ie CDs, USB sticks & Samba shares.
+//! The time is shown in the data field of line 1, as the time gone, the time left or both, & a bar on a line chosen in the configuration.
+//! rradio only sends the position now & then, so the position is worked out locally in between, so that the time goes up every second.
+
+use rradio_messages::PipelineState;
+use serde::Deserialize;
+use std::time::Duration;
+use tokio::time::Instant;
+
+use crate::compositor::{Compositor, Layer, LayerId, Priority};
+use crate::lcd::{LineNum, LINE1_DATA_CHAR_COUNT_USIZE, NUM_CHARACTERS_PER_LINE};
+use crate::player_model::PlayerModel;
+use crate::render::bar;
+
+const ONE_SECOND: Duration = Duration::from_secs(1);
+
+/// TimeShown is which time is shown on line 1
+#[derive(PartialEq, Eq, Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TimeShown {
+    Elapsed,   // eg "3: 01:23"
+    Remaining, // eg "3: -02:05"
+    Both,      // eg "01:23 -02:05"
+}
+
+/// ProgressConfig is the configuration of how the progress through the track is shown, eg
+/// ```toml
+/// [progress]
+/// time_shown = "remaining"
+/// bar_line = 0
+/// ```
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct ProgressConfig {
+    pub time_shown: TimeShown,
+    /// the line the bar goes on, from 1 to 4, or 0 for no bar. The bar is not shown if the title of the track is on that line.
+    pub bar_line: u8,
+}
+
+impl Default for ProgressConfig {
+    fn default() -> Self {
+        ProgressConfig {
+            time_shown: TimeShown::Both,
+            bar_line: 4,
+        }
+    }
+}
+
+/// format_time returns the time as "mm:ss", or as "h:mm:ss" if it is an hour or more
+pub fn format_time(time: Duration) -> String {
+    let secs = time.as_secs();
+    let (hours, minutes, seconds) = (secs / 3600, secs / 60 % 60, secs % 60);
+    if hours > 0 {
+        format!("{}:{:02}:{:02}", hours, minutes, seconds)
+    } else {
+        format!("{:02}:{:02}", minutes, seconds)
+    }
+}
+
+/// time_text returns the time to go in the data field of line 1. If the time wanted does not fit, the track number
+/// is left out, & if it still does not fit, only the time gone is shown.
+fn time_text(
+    track_number: usize,
+    position: Duration,
+    duration: Option<Duration>,
+    time_shown: TimeShown,
+) -> String {
+    let elapsed = format_time(position);
+    let remaining = duration.map(|duration| {
+        format!(
+            "-{}",
+            format_time(Duration::from_secs(
+                duration.as_secs().saturating_sub(position.as_secs()), // so that the two times always add up to the duration
+            ))
+        )
+    });
+    let time = match (time_shown, remaining) {
+        (TimeShown::Elapsed, _) | (_, None) => elapsed.clone(), // without a duration, the time left is not known
+        (TimeShown::Remaining, Some(remaining)) => remaining,
+        (TimeShown::Both, Some(remaining)) => format!("{} {}", elapsed, remaining),
+    };
+    vec![format!("{}: {}", track_number, time), time, elapsed]
+        .into_iter()
+        .find(|candidate| candidate.chars().count() <= LINE1_DATA_CHAR_COUNT_USIZE)
+        .unwrap_or_default()
+}
+
+/// bar_line returns the line the bar goes on, or None if there is no bar or the title of the track is on the line chosen
+fn bar_line(model: &PlayerModel, config: &ProgressConfig) -> Option<LineNum> {
+    let title_length = model
+        .track
+        .as_ref()
+        .map_or(0, |track| track.title.chars().count());
+    let (line_number, title_is_there) = match config.bar_line {
+        1 => (LineNum::Line1, false),
+        2 => (LineNum::Line2, false),
+        3 => (LineNum::Line3, title_length > 0),
+        4 => (LineNum::Line4, title_length > NUM_CHARACTERS_PER_LINE), // long titles carry on onto line 4
+        _ => return None,
+    };
+    if title_is_there {
+        None
+    } else {
+        Some(line_number)
+    }
+}
+
+/// progress_layer returns the time in the data field of line 1 & the bar on the line chosen, or None if the track has no position,
+/// eg because a stream is playing
+pub fn progress_layer(model: &PlayerModel, config: &ProgressConfig, now: Instant) -> Option<Layer> {
+    let position = model.track_position_at(now)?;
+    let mut layer = Layer::new();
+    layer.write_multiline(
+        LineNum::Line1,
+        LINE1_DATA_CHAR_COUNT_USIZE,
+        &time_text(
+            model.current_track_index + 1, // humans count from 1
+            position,
+            model.track_duration,
+            config.time_shown,
+        ),
+    );
+    if let (Some(line_number), Some(duration)) = (bar_line(model, config), model.track_duration) {
+        let steps = match duration.as_millis() {
+            0 => 0,
+            duration_millis => position.as_millis() * 100 / duration_millis,
+        };
+        layer.write_multiline(
+            line_number,
+            NUM_CHARACTERS_PER_LINE,
+            &bar(steps.min(100) as i32),
+        );
+    }
+    Some(layer)
+}
+
+/// next_tick returns when the position next reaches a whole second, so that the time shown can go up then,
+/// or None if the position is not moving
+pub fn next_tick(model: &PlayerModel, now: Instant) -> Option<Instant> {
+    let position = model.track_position_at(now)?;
+    if model.pipe_line_state != PipelineState::Playing || Some(position) == model.track_duration {
+        return None;
+    }
+    let into_second = Duration::from_nanos(u64::from(position.subsec_nanos()));
+    Some(now + (ONE_SECOND - into_second))
+}
+
+/// draw_progress puts the progress through the track into the compositor, or takes it out if the track has no position
+pub fn draw_progress(
+    compositor: &mut Compositor,
+    model: &PlayerModel,
+    config: &ProgressConfig,
+    now: Instant,
+) {
+    match progress_layer(model, config, now) {
+        Some(layer) => compositor.set(LayerId::Progress, Priority::Status, layer, None),
+        None => compositor.remove(LayerId::Progress),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lcd::Frame;
+    use crate::metadata::TrackMetadata;
+
+    fn usb_model(now: Instant, title: &str) -> PlayerModel {
+        let mut model = PlayerModel::default();
+        model.pipe_line_state = PipelineState::Playing;
+        model.current_track_index = 2;
+        model.track_duration = Some(Duration::from_secs(200));
+        model.track_position = Some(Duration::from_secs(83));
+        model.track_position_time = Some(now);
+        model.track = Some(TrackMetadata {
+            title: title.to_string(),
+            ..TrackMetadata::default()
+        });
+        model
+    }
+
+    fn frame(model: &PlayerModel, config: &ProgressConfig, now: Instant) -> Frame {
+        let mut frame = Frame::new();
+        progress_layer(model, config, now).unwrap().draw(&mut frame);
+        frame
+    }
+
+    #[test]
+    fn times_are_in_minutes_and_seconds() {
+        assert_eq!(format_time(Duration::from_secs(83)), "01:23");
+        assert_eq!(format_time(Duration::from_secs(3723)), "1:02:03");
+        let position = Duration::from_secs(83);
+        let duration = Some(Duration::from_secs(208));
+        assert_eq!(
+            time_text(3, position, duration, TimeShown::Elapsed),
+            "3: 01:23"
+        );
+        assert_eq!(
+            time_text(3, position, duration, TimeShown::Remaining),
+            "3: -02:05"
+        );
+        assert_eq!(
+            time_text(3, position, duration, TimeShown::Both),
+            "01:23 -02:05"
+        );
+        assert_eq!(
+            time_text(3, position, None, TimeShown::Remaining),
+            "3: 01:23"
+        );
+        assert_eq!(
+            time_text(
+                3,
+                position + Duration::from_millis(500),
+                duration,
+                TimeShown::Both
+            ),
+            "01:23 -02:05"
+        );
+        assert_eq!(
+            time_text(
+                12,
+                Duration::from_secs(3723),
+                Some(Duration::from_secs(7200)),
+                TimeShown::Both
+            ),
+            "1:02:03" // "1:02:03 -0:57:57" does not fit
+        );
+    }
+
+    #[test]
+    fn the_time_goes_up_between_positions_from_rradio() {
+        let now = Instant::now();
+        let config = ProgressConfig {
+            time_shown: TimeShown::Elapsed,
+            ..ProgressConfig::default()
+        };
+        let mut model = usb_model(now, "Short title");
+        assert_eq!(
+            frame(&model, &config, now).line(LineNum::Line1).trim_end(),
+            "3: 01:23"
+        );
+        assert_eq!(next_tick(&model, now), Some(now + ONE_SECOND));
+
+        let later = now + Duration::from_millis(2500);
+        assert_eq!(
+            frame(&model, &config, later)
+                .line(LineNum::Line1)
+                .trim_end(),
+            "3: 01:25"
+        );
+        assert_eq!(
+            next_tick(&model, later),
+            Some(later + Duration::from_millis(500))
+        );
+
+        model.pipe_line_state = PipelineState::Paused;
+        assert_eq!(
+            frame(&model, &config, later)
+                .line(LineNum::Line1)
+                .trim_end(),
+            "3: 01:23"
+        );
+        assert_eq!(next_tick(&model, later), None);
+    }
+
+    #[test]
+    fn the_bar_is_not_drawn_over_the_title() {
+        let now = Instant::now();
+        let config = ProgressConfig::default();
+        let model = usb_model(now, "Short title");
+        assert_eq!(
+            frame(&model, &config, now).line(LineNum::Line4),
+            format!("{}\x00{}", "█".repeat(8), " ".repeat(11)) // 83 of 200 seconds is 41 steps
+        );
+
+        let model = usb_model(now, "A title that is too long for one line");
+        assert_eq!(
+            frame(&model, &config, now).line(LineNum::Line4).trim_end(),
+            ""
+        );
+
+        let config = ProgressConfig {
+            bar_line: 0,
+            ..ProgressConfig::default()
+        };
+        assert_eq!(
+            frame(&model, &config, now).line(LineNum::Line4).trim_end(),
+            ""
+        );
+        assert!(progress_layer(&PlayerModel::default(), &config, now).is_none());
+    }
+}
@@ -0,0 +1,201 @@
+use futures_util::{Stream, StreamExt};
+use rradio_messages::Event;
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tokio::time::Instant;
+
+/// Mode is whether the events from rradio are shown live, shown & recorded, or replayed from a recording,
+/// as set by "--record <file>" or "--replay <file> [--speed <factor>]" on the command line
+#[derive(PartialEq, Debug, Clone)]
+pub enum Mode {
+    Live,
+    Record(PathBuf),
+    Replay { path: PathBuf, speed: f64 }, // a speed of 2 replays twice as fast as the events originally arrived
+}
+
+/// mode_from_command_line returns the mode given on the command line, or an error if it is not valid
+pub fn mode_from_command_line(mut args: impl Iterator<Item = String>) -> Result<Mode, String> {
+    let mut mode = Mode::Live;
+    let mut speed = 1.0;
+    while let Some(arg) = args.next() {
+        let mut value = |what: &str| {
+            args.next()
+                .ok_or_else(|| format!("{} needs to be followed by {}", arg, what))
+        };
+        match arg.as_str() {
+            "--record" => mode = Mode::Record(PathBuf::from(value("a file name")?)),
+            "--replay" => {
+                mode = Mode::Replay {
+                    path: PathBuf::from(value("a file name")?),
+                    speed: 1.0,
+                }
+            }
+            "--speed" => {
+                let factor = value("a number")?;
+                speed = match factor.parse() {
+                    Ok(speed) if speed > 0.0 => speed,
+                    _ => return Err(format!("\"{}\" is not a valid speed", factor)),
+                };
+            }
+            _ => {} // the other arguments are not about recording
+        }
+    }
+    match mode {
+        Mode::Replay { path, .. } => Ok(Mode::Replay { path, speed }),
+        Mode::Live | Mode::Record(_) if speed != 1.0 => {
+            Err("--speed can only be used with --replay".to_string())
+        }
+        mode => Ok(mode),
+    }
+}
+
+/// RecordedEvent is one event as it was recorded, with how long after the start of the recording it arrived
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordedEvent {
+    pub millis: u64,
+    pub event: Event,
+}
+
+/// Recorder writes every event to a file as it arrives. The file starts with the rradio-messages header,
+/// so that a recording made with a different version is not misread, followed by each event encoded in the same way as rradio encodes it.
+pub struct Recorder {
+    file: std::io::BufWriter<std::fs::File>,
+    start: Instant,
+}
+
+impl Recorder {
+    /// create starts a new recording, overwriting the file if it already exists
+    pub fn create(path: &Path, now: Instant) -> std::io::Result<Recorder> {
+        let mut file = std::io::BufWriter::new(std::fs::File::create(path)?);
+        file.write_all(rradio_messages::API_VERSION_HEADER.as_bytes())?;
+        file.flush()?;
+        Ok(Recorder { file, start: now })
+    }
+
+    /// record adds the event to the recording. The file is flushed straight away, so nothing is lost if the program is stopped.
+    pub fn record(&mut self, event: &Event, now: Instant) -> std::io::Result<()> {
+        let recorded_event = RecordedEvent {
+            millis: now.saturating_duration_since(self.start).as_millis() as u64,
+            event: event.clone(),
+        };
+        let encoded = postcard::to_stdvec_cobs(&recorded_event)
+            .map_err(|error| std::io::Error::new(std::io::ErrorKind::InvalidData, error))?;
+        self.file.write_all(&encoded)?;
+        self.file.flush()
+    }
+}
+
+/// load reads a recording made by Recorder
+pub fn load(path: &Path) -> Result<Vec<RecordedEvent>, anyhow::Error> {
+    let mut contents = std::fs::read(path)
+        .map_err(|error| anyhow::anyhow!("Could not read {}: {}", path.display(), error))?;
+    let header = rradio_messages::API_VERSION_HEADER.as_bytes();
+    if !contents.starts_with(header) {
+        let recorded_header = contents
+            .split(|byte| *byte == b'\n')
+            .next()
+            .unwrap_or_default();
+        return Err(anyhow::anyhow!(
+            "{} was not recorded with {}, but with {}",
+            path.display(),
+            header.escape_ascii(),
+            recorded_header.escape_ascii()
+        ));
+    }
+    let mut remaining = &mut contents[header.len()..];
+    let mut recording = Vec::new();
+    while !remaining.is_empty() {
+        let (recorded_event, rest) = postcard::take_from_bytes_cobs::<RecordedEvent>(remaining)
+            .map_err(|error| {
+                anyhow::anyhow!(
+                    "{} is damaged after {} events: {}",
+                    path.display(),
+                    recording.len(),
+                    error
+                )
+            })?;
+        recording.push(recorded_event);
+        remaining = rest;
+    }
+    Ok(recording)
+}
+
+/// replay returns the events in the recording, each one arriving at the same time after the start as when it was recorded,
+/// divided by the speed. It can be shown by the driver in the same way as the events from rradio.
+pub fn replay(
+    recording: Vec<RecordedEvent>,
+    speed: f64,
+) -> impl Stream<Item = Result<Event, std::convert::Infallible>> {
+    let start = Instant::now();
+    futures_util::stream::iter(recording).then(move |recorded_event| async move {
+        tokio::time::sleep_until(
+            start + Duration::from_millis(recorded_event.millis).div_f64(speed),
+        )
+        .await;
+        Ok(recorded_event.event)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rradio_messages::PlayerStateDiff;
+
+    #[test]
+    fn the_mode_is_read_from_the_command_line() {
+        let mode = |args: &[&str]| mode_from_command_line(args.iter().map(|arg| arg.to_string()));
+        assert_eq!(mode(&["--rradio", "10.0.0.2"]), Ok(Mode::Live));
+        assert_eq!(
+            mode(&["--record", "bug.rec"]),
+            Ok(Mode::Record(PathBuf::from("bug.rec")))
+        );
+        assert_eq!(
+            mode(&["--speed", "10", "--replay", "bug.rec"]),
+            Ok(Mode::Replay {
+                path: PathBuf::from("bug.rec"),
+                speed: 10.0
+            })
+        );
+        assert!(mode(&["--record"]).is_err());
+        assert!(mode(&["--replay", "bug.rec", "--speed", "0"]).is_err());
+        assert!(mode(&["--record", "bug.rec", "--speed", "2"]).is_err());
+    }
+
+    #[tokio::test]
+    async fn a_recording_is_replayed_with_the_same_timing() {
+        let path = std::env::temp_dir().join(format!("rradio_lcd_test_{}.rec", std::process::id()));
+        let start = Instant::now();
+        let mut recorder = Recorder::create(&path, start).unwrap();
+        let volume = |volume| {
+            Event::PlayerStateChanged(PlayerStateDiff {
+                volume: Some(volume),
+                ..PlayerStateDiff::default()
+            })
+        };
+        recorder.record(&volume(70), start).unwrap();
+        recorder
+            .record(&volume(75), start + Duration::from_millis(400))
+            .unwrap();
+        drop(recorder);
+
+        let recording = load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(
+            recording
+                .iter()
+                .map(|recorded_event| recorded_event.millis)
+                .collect::<Vec<_>>(),
+            vec![0, 400]
+        );
+
+        let replay_start = Instant::now();
+        let replayed: Vec<_> = replay(recording, 4.0).collect().await;
+        assert!(replay_start.elapsed() >= Duration::from_millis(100)); // 400ms at 4 times the speed
+        assert_eq!(
+            format!("{:?}", replayed[1]),
+            format!("{:?}", Ok::<_, ()>(volume(75)))
+        );
+    }
+}
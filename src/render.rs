@@ -0,0 +1,586 @@
+use chrono::{DateTime, FixedOffset};
+use rradio_messages::{PipelineState, StationType};
+use std::time::Duration;
+use tokio::time::Instant;
+
+use crate::clock::Clock;
+use crate::compositor::{Compositor, Layer, LayerId, Priority};
+use crate::error_list::{ErrorState, ReportedError};
+use crate::lcd::big_digits::{big_number, big_number_width, FULL_BLOCK};
+use crate::lcd::{
+    scroll_position, Frame, LineNum, LINE1_DATA_CHAR_COUNT_USIZE, NUM_CHARACTERS_PER_LINE,
+    VOLUME_CHAR_COUNT,
+};
+use crate::localisation::{format_date_time, text, MessageId};
+use crate::metadata::assemble_line2;
+use crate::player_model::PlayerModel;
+use crate::status_text::{StatusText, VolumeStatus};
+
+/// how often text that is too long to fit scrolls
+pub const SCROLL_PERIOD: Duration = Duration::from_millis(1600);
+/// how long text is shown before it starts to scroll, so that people can read the start of it
+pub const SCROLL_DELAY: Duration = Duration::from_millis(3000);
+/// how long the station is shown on line 1 before the ping times are shown instead
+const PING_DELAY_AFTER_STATION_CHANGE: Duration = Duration::from_secs(6);
+
+/// Moment is the time at which the screen is drawn, both as an instant (for timing) & as the local date & time (for showing)
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub struct Moment {
+    pub instant: Instant,
+    pub date_time: DateTime<FixedOffset>,
+}
+
+impl Moment {
+    /// at returns the moment the clock says it is now
+    pub fn at(clock: &dyn Clock) -> Self {
+        Moment {
+            instant: clock.now(),
+            date_time: clock.date_time(),
+        }
+    }
+}
+
+/// render draws the screen showing the state of the player at the moment specified. It does not read or write anything,
+/// so what is shown can be tested without a screen or rradio.
+pub fn render(model: &PlayerModel, now: &Moment) -> Frame {
+    let mut compositor = Compositor::default();
+    render_layers(model, now, &mut compositor);
+    compositor.compose(now.instant)
+}
+
+/// render_layers puts the layers showing the state of the player into the compositor: what is playing, the state of the buffer,
+/// & the most important error, each of which is removed if there is nothing to show
+pub fn render_layers(model: &PlayerModel, now: &Moment, compositor: &mut Compositor) {
+    let mut playing = Layer::new();
+    let mut buffer = None;
+    let mut error_layer = None;
+    match model.errors.most_important(now.instant) {
+        None if !model.started_up => render_starting_up(model, now, &mut playing),
+        None => {
+            render_now_playing(model, now, &mut playing);
+            buffer = buffer_layer(model);
+        }
+        Some(error) if error.description.state == ErrorState::NoStation => {
+            render_no_station(model, now, &mut playing)
+        }
+        Some(error) => error_layer = Some(render_error(model, error, now)),
+    }
+    compositor.set(LayerId::Playing, Priority::Background, playing, None);
+    for (id, priority, layer) in [
+        (LayerId::Buffer, Priority::Status, buffer),
+        (LayerId::Error, Priority::Error, error_layer),
+    ] {
+        match layer {
+            Some(layer) => compositor.set(id, priority, layer, None),
+            None => compositor.remove(id),
+        }
+    }
+}
+
+/// render_error draws the error, which takes all 4 lines: what went wrong, the details, which scroll if they are too long,
+/// & what to do about it
+fn render_error(model: &PlayerModel, error: &ReportedError, now: &Moment) -> Layer {
+    let description = &error.description;
+    let mut layer = Layer::new();
+    layer.write_multiline(
+        LineNum::Line1,
+        NUM_CHARACTERS_PER_LINE,
+        &description.headline,
+    );
+    layer.write_multiline(
+        LineNum::Line2,
+        NUM_CHARACTERS_PER_LINE,
+        scrolled(
+            &description.explanation,
+            NUM_CHARACTERS_PER_LINE,
+            Some(error.first_reported),
+            now,
+        ),
+    );
+    layer.write_multiline(
+        LineNum::Line3,
+        NUM_CHARACTERS_PER_LINE * 2,
+        &description.remedy,
+    );
+
+    // say how many other errors there are at the end of the last line
+    let other_count = model.errors.other_count(now.instant);
+    if other_count > 0 {
+        let other_errors = text(MessageId::OtherErrors, &[&other_count]);
+        layer.write(
+            LineNum::Line4,
+            NUM_CHARACTERS_PER_LINE.saturating_sub(other_errors.chars().count()),
+            &other_errors,
+        );
+    }
+    layer
+}
+
+/// render_starting_up draws the screen shown until the first station starts playing
+fn render_starting_up(model: &PlayerModel, now: &Moment, layer: &mut Layer) {
+    layer.write_multiline(
+        LineNum::Line1,
+        LINE1_DATA_CHAR_COUNT_USIZE,
+        &model.system.ip_address,
+    );
+    if model.volume < 0 {
+        layer.write(
+            LineNum::Line1,
+            LINE1_DATA_CHAR_COUNT_USIZE,
+            "\x00\x01 \x02\x03 \x04\x05",
+        ); // show off the bespoke characters
+    } else {
+        layer.write(
+            LineNum::Line1,
+            LINE1_DATA_CHAR_COUNT_USIZE,
+            &volume_field(model),
+        );
+    }
+    if let Some(ping_message) = ping_message(model, NUM_CHARACTERS_PER_LINE) {
+        layer.write_multiline(LineNum::Line2, NUM_CHARACTERS_PER_LINE, &ping_message);
+    }
+    layer.write_multiline(
+        LineNum::Line3,
+        NUM_CHARACTERS_PER_LINE,
+        &format_date_time(MessageId::DateAndTime, &now.date_time),
+    );
+    layer.write_multiline(
+        LineNum::Line4,
+        NUM_CHARACTERS_PER_LINE,
+        &text(
+            MessageId::TemperatureAndTime,
+            &[
+                &model.system.cpu_temperature,
+                &format_date_time(MessageId::TimeOfDay, &now.date_time),
+            ],
+        ),
+    );
+}
+
+/// render_no_station draws the screen shown when the channel asked for does not exist
+fn render_no_station(model: &PlayerModel, now: &Moment, layer: &mut Layer) {
+    layer.write_multiline(
+        LineNum::Line1,
+        NUM_CHARACTERS_PER_LINE,
+        &text(MessageId::NoStation, &[&model.channel]),
+    );
+    layer.write_multiline(
+        LineNum::Line3,
+        NUM_CHARACTERS_PER_LINE,
+        &format_date_time(MessageId::DateAndTime, &now.date_time),
+    );
+    if model.channel_not_found_twice {
+        // asking for the same missing channel twice is the way to ask for the version & to test the screen
+        layer.write_multiline(
+            LineNum::Line2,
+            NUM_CHARACTERS_PER_LINE,
+            &text(MessageId::LcdVersion, &[&env!("CARGO_PKG_VERSION")]),
+        );
+        layer.write_multiline(
+            LineNum::Line4,
+            NUM_CHARACTERS_PER_LINE,
+            "\x00 \x01 \x02 \x03 \x04\x05\x06\x07ñäöüÆÇç",
+        );
+    } else {
+        layer.write_multiline(
+            LineNum::Line2,
+            NUM_CHARACTERS_PER_LINE,
+            &model.system.ip_address,
+        );
+        layer.write_multiline(
+            LineNum::Line4,
+            NUM_CHARACTERS_PER_LINE,
+            &temperature_and_strength(model),
+        );
+    }
+}
+
+/// render_now_playing draws the normal screen: the station or the ping time & the volume on line 1, the station,
+/// artist & album on line 2, & the title of the track on lines 3 & 4
+fn render_now_playing(model: &PlayerModel, now: &Moment, layer: &mut Layer) {
+    let station_has_been_shown = model
+        .station_change_time
+        .map_or(true, |station_change_time| {
+            now.instant.saturating_duration_since(station_change_time)
+                >= PING_DELAY_AFTER_STATION_CHANGE
+        });
+    let line1_data = match ping_message(model, LINE1_DATA_CHAR_COUNT_USIZE) {
+        Some(ping_message)
+            if station_has_been_shown && model.pipe_line_state == PipelineState::Playing =>
+        {
+            ping_message
+        }
+        _ => station_label(model), // the progress through the track goes over this, for sources with tracks that have a length
+    };
+    layer.write_multiline(LineNum::Line1, LINE1_DATA_CHAR_COUNT_USIZE, &line1_data);
+    layer.write(
+        LineNum::Line1,
+        LINE1_DATA_CHAR_COUNT_USIZE,
+        &volume_field(model),
+    );
+
+    layer.write_multiline(
+        LineNum::Line2,
+        NUM_CHARACTERS_PER_LINE,
+        scrolled(
+            &line2_text(model),
+            NUM_CHARACTERS_PER_LINE,
+            model.scroll_start_time,
+            now,
+        ),
+    );
+
+    let song_title = model
+        .track
+        .as_ref()
+        .map(|track| track.title.as_str())
+        .unwrap_or_default();
+    if song_title.is_empty() {
+        // we have space to write the temperature
+        layer.write_multiline(
+            LineNum::Line3,
+            NUM_CHARACTERS_PER_LINE,
+            &temperature_and_strength(model),
+        );
+    } else {
+        layer.write_multiline(
+            LineNum::Line3,
+            NUM_CHARACTERS_PER_LINE * 2,
+            scrolled(
+                song_title,
+                NUM_CHARACTERS_PER_LINE * 2,
+                model.scroll_start_time,
+                now,
+            ),
+        );
+    }
+}
+
+/// buffer_layer returns the state of the buffer on line 4, if the title of the track leaves room for it
+fn buffer_layer(model: &PlayerModel) -> Option<Layer> {
+    let buffering = model.buffering?;
+    let song_title_length = model
+        .track
+        .as_ref()
+        .map_or(0, |track| track.title.chars().count());
+    if song_title_length > NUM_CHARACTERS_PER_LINE || model.station_type == StationType::CD {
+        return None; // no need to write the buffer state for CDs
+    }
+    let mut layer = Layer::new();
+    layer.write_multiline(
+        LineNum::Line4,
+        NUM_CHARACTERS_PER_LINE,
+        &buffer_state_line(buffering),
+    );
+    Some(layer)
+}
+
+/// volume_layer returns the volume in big digits on lines 2 & 3, with "Volume" beside it & "Muted" underneath if it is muted,
+/// & a bar as long as the volume across line 4, so that it can be read from across the room while the volume is being changed
+pub fn volume_layer(model: &PlayerModel) -> Layer {
+    let volume = model.volume.clamp(0, 100);
+    let mut layer = Layer::new();
+    let digits = volume.to_string();
+    let big_digits_column = NUM_CHARACTERS_PER_LINE - big_number_width(digits.len());
+    let [top_half, bottom_half] = big_number(&digits);
+    layer.write_multiline(
+        LineNum::Line2,
+        NUM_CHARACTERS_PER_LINE,
+        &text(MessageId::VolumeLabel, &[]),
+    );
+    layer.write(LineNum::Line2, big_digits_column, &top_half);
+    let muted = if model.is_muted {
+        text(MessageId::Muted, &[])
+    } else {
+        String::new()
+    };
+    layer.write_multiline(LineNum::Line3, NUM_CHARACTERS_PER_LINE, &muted);
+    layer.write(LineNum::Line3, big_digits_column, &bottom_half);
+    layer.write_multiline(LineNum::Line4, NUM_CHARACTERS_PER_LINE, &bar(volume));
+    layer
+}
+
+/// bar returns a bar across the whole line as long as the number of steps, from 0 to 100, eg the volume. Each character is 5 steps,
+/// & the end of the bar is shown to the step by the bespoke characters with a line in one of their 5 columns.
+pub fn bar(steps: i32) -> String {
+    let full_blocks = (steps / 5) as usize;
+    let mut bar = FULL_BLOCK.to_string().repeat(full_blocks);
+    let steps_left = (steps % 5) as u8;
+    if steps_left > 0 {
+        bar.push(char::from(steps_left - 1));
+    }
+    bar
+}
+
+/// station_label returns the type of station & the channel, eg "Station 07", to go in line 1
+pub fn station_label(model: &PlayerModel) -> String {
+    match model.station_type {
+        StationType::UPnP | StationType::UrlList => text(MessageId::Station, &[&model.channel]),
+        StationType::CD => text(MessageId::PlayingCd, &[]),
+        StationType::Usb => text(MessageId::Usb, &[&model.channel]),
+        StationType::SambaShare => text(MessageId::File, &[&model.channel]),
+    }
+}
+
+/// volume_field returns the volume (or the gstreamer state if not playing, or "Muted") to go at the end of line 1
+fn volume_field(model: &PlayerModel) -> String {
+    let volume_status = VolumeStatus {
+        pipe_line_state: model.pipe_line_state,
+        is_muted: model.is_muted,
+        volume: model.volume,
+    };
+    let volume_text = volume_status.fit(VOLUME_CHAR_COUNT);
+    if volume_status.is_showing_volume() {
+        format!("{:>Width$}", volume_text, Width = VOLUME_CHAR_COUNT) // right justify the volume or "Muted"
+    } else {
+        format!("{:<Width$}", volume_text, Width = VOLUME_CHAR_COUNT) // left justify the gstreamer state
+    }
+}
+
+/// ping_message returns the latest ping time, or the CPU temperature when that is being shown instead, or None if there has not been a ping
+fn ping_message(model: &PlayerModel, width: usize) -> Option<String> {
+    if model.show_temperature_instead_of_gateway_ping {
+        Some(text(
+            MessageId::CpuTemperature,
+            &[&format!("{:>3}", model.system.cpu_temperature)],
+        ))
+    } else {
+        model.ping_status.map(|ping_status| ping_status.fit(width))
+    }
+}
+
+/// temperature_and_strength returns the CPU temperature & the Wi-Fi signal strength
+fn temperature_and_strength(model: &PlayerModel) -> String {
+    text(
+        MessageId::TemperatureAndStrength,
+        &[&model.system.cpu_temperature, &model.system.wifi_strength],
+    )
+}
+
+/// line2_text returns the text for line 2: the CD track number on a CD without tags, otherwise the organisation
+/// (or the station title), artist & album
+fn line2_text(model: &PlayerModel) -> String {
+    match &model.track {
+        None if model.station_type == StationType::CD => text(
+            MessageId::CdTrack,
+            &[&(model.current_track_index + 1), &model.number_of_tracks],
+        ),
+        None => assemble_line2("", &model.station_title, "", "", model.pause_before_playing),
+        Some(track) => {
+            let organisation = if model.current_track_index == 0 || track.organisation.is_empty() {
+                track.organisation.clone()
+            } else {
+                format!("{} {}", model.current_track_index + 1, track.organisation)
+            };
+            assemble_line2(
+                &organisation,
+                &model.station_title,
+                &track.artist,
+                &track.album,
+                model.pause_before_playing,
+            )
+        }
+    }
+}
+
+/// scrolled returns the part of the text to show, which is all of it if it fits, otherwise it scrolls one step
+/// every SCROLL_PERIOD, after it has been shown for SCROLL_DELAY from the start time
+fn scrolled<'a>(
+    string: &'a str,
+    width: usize,
+    scroll_start_time: Option<Instant>,
+    now: &Moment,
+) -> &'a str {
+    if string.chars().count() <= width {
+        return string;
+    }
+    let steps = scroll_start_time.map_or(0, |scroll_start_time| {
+        match now
+            .instant
+            .saturating_duration_since(scroll_start_time)
+            .checked_sub(SCROLL_DELAY)
+        {
+            Some(time_scrolling) => {
+                1 + (time_scrolling.as_millis() / SCROLL_PERIOD.as_millis()) as usize
+            }
+            None => 0,
+        }
+    });
+    &string[scroll_position(string, steps)..]
+}
+
+/// buffer_state_line returns a line with a cursor showing how full the gsteamer buffer is
+fn buffer_state_line(buffer_position: u8) -> String {
+    let trimmed_buffer = buffer_position.min(99); // 0 to 100 is 101 values, & the screen only handles 100 values, so trim downwards
+    let scaled_buffer = (trimmed_buffer / 5) as usize; // the characters have 5 columns
+    let cursor = char::from(trimmed_buffer % 5); // the bespoke characters 0 to 4 have a bar in each of the 5 columns
+    format!(
+        "{}{}{}",
+        " ".repeat(scaled_buffer),
+        cursor,
+        " ".repeat(NUM_CHARACTERS_PER_LINE - scaled_buffer - 1)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::errors::{describe_cd_error, describe_error, describe_station_error};
+    use crate::metadata::TrackMetadata;
+    use crate::system_info::SystemReadings;
+    use chrono::TimeZone;
+
+    fn moment(instant: Instant) -> Moment {
+        Moment {
+            instant,
+            date_time: FixedOffset::east_opt(0)
+                .unwrap()
+                .with_ymd_and_hms(2026, 3, 14, 15, 9, 26)
+                .unwrap(),
+        }
+    }
+
+    fn playing_model(start: Instant) -> PlayerModel {
+        let mut model = PlayerModel::default();
+        model.started_up = true;
+        model.pipe_line_state = PipelineState::Playing;
+        model.volume = 70;
+        model.station_type = StationType::UrlList;
+        model.channel = "07".to_string();
+        model.station_title = "Jazz FM".to_string();
+        model.station_change_time = Some(start);
+        model.scroll_start_time = Some(start);
+        model.system = SystemReadings {
+            ip_address: "192.168.1.20".to_string(),
+            cpu_temperature: 45,
+            wifi_strength: "-52".to_string(),
+            ..SystemReadings::default()
+        };
+        model
+    }
+
+    #[test]
+    fn now_playing_shows_the_station_volume_and_track() {
+        let start = Instant::now();
+        let mut model = playing_model(start);
+        model.track = Some(TrackMetadata {
+            title: "So What".to_string(),
+            artist: "Miles Davis".to_string(),
+            ..TrackMetadata::default()
+        });
+        model.buffering = Some(50);
+        let frame = render(&model, &moment(start));
+        assert_eq!(frame.line(LineNum::Line1), "Station 07   Vol  70");
+        assert_eq!(frame.line(LineNum::Line2), "Jazz FM / Miles Davi");
+        assert_eq!(frame.line(LineNum::Line3), "So What             ");
+        assert_eq!(
+            frame.line(LineNum::Line4),
+            format!("{}\x00{}", " ".repeat(10), " ".repeat(9))
+        );
+    }
+
+    #[test]
+    fn long_text_scrolls_after_a_delay() {
+        let start = Instant::now();
+        let mut model = playing_model(start);
+        model.station_title = "The Jazz Station From Somewhere Far Away".to_string();
+        let line2_at = |seconds| {
+            render(&model, &moment(start + Duration::from_secs(seconds))).line(LineNum::Line2)
+        };
+        assert_eq!(line2_at(0), "The Jazz Station Fro");
+        assert_eq!(line2_at(2), "The Jazz Station Fro");
+        assert_eq!(line2_at(3), "Station From Somewhe");
+    }
+
+    #[test]
+    fn no_station_shows_the_channel_and_the_date() {
+        let start = Instant::now();
+        let mut model = playing_model(start);
+        model.errors.report(
+            describe_station_error(&rradio_messages::StationError::StationNotFound {
+                index: rradio_messages::StationIndex::new("42".to_string()),
+                directory: "/home/pi/stations".into(),
+            }),
+            start,
+        );
+        model.channel = "42".to_string();
+        let frame = render(&model, &moment(start));
+        assert_eq!(frame.line(LineNum::Line1), "No station 42       ");
+        assert_eq!(frame.line(LineNum::Line2), "192.168.1.20        ");
+        assert_eq!(frame.line(LineNum::Line3), "  14 Mar 26 15:09:26");
+    }
+
+    #[test]
+    fn errors_take_the_whole_screen() {
+        let start = Instant::now();
+        let mut model = playing_model(start);
+        model.errors.report(
+            describe_cd_error(&rradio_messages::CdError::CdTrayIsOpen),
+            start,
+        );
+        assert_eq!(
+            render(&model, &moment(start)).to_string(),
+            format!(
+                "CD tray open{}\n{}\nClose the CD tray{}\n{}",
+                " ".repeat(8),
+                " ".repeat(20),
+                " ".repeat(3),
+                " ".repeat(20)
+            )
+        );
+    }
+
+    #[test]
+    fn the_number_of_other_errors_is_shown() {
+        let start = Instant::now();
+        let mut model = playing_model(start);
+        model.errors.report(
+            describe_error(&rradio_messages::Error::PipelineError(
+                "Internal data stream error".into(),
+            )),
+            start,
+        );
+        model.errors.report(
+            describe_station_error(&rradio_messages::StationError::BadStationFile(
+                "03 Radio 3: missing URL".into(),
+            )),
+            start,
+        );
+        let frame = render(&model, &moment(start));
+        assert_eq!(frame.line(LineNum::Line1), "Bad station file    ");
+        assert_eq!(frame.line(LineNum::Line2), "03 Radio 3: missing ");
+        assert!(frame.line(LineNum::Line4).ends_with(" +1 more"));
+    }
+
+    #[test]
+    fn the_volume_is_shown_big() {
+        let start = Instant::now();
+        let mut model = playing_model(start);
+        model.volume = 42;
+        model.is_muted = true;
+        let mut frame = Frame::new();
+        volume_layer(&model).draw(&mut frame);
+        assert_eq!(frame.line(LineNum::Line2), "Volume       █▄█ ≡≡█");
+        assert_eq!(frame.line(LineNum::Line3), "Muted          █ █▄▄");
+        assert_eq!(
+            frame.line(LineNum::Line4),
+            format!("{}\x01{}", "█".repeat(8), " ".repeat(11))
+        );
+        assert_eq!(bar(100), "█".repeat(20));
+    }
+
+    #[test]
+    fn ping_replaces_the_station_after_a_while() {
+        let start = Instant::now();
+        let mut model = playing_model(start);
+        model.ping_status = Some(crate::status_text::PingStatus::Time {
+            target: rradio_messages::PingTarget::Gateway,
+            time: Duration::from_micros(1234),
+        });
+        let line1_at = |seconds| {
+            render(&model, &moment(start + Duration::from_secs(seconds))).line(LineNum::Line1)
+        };
+        assert_eq!(line1_at(1), "Station 07   Vol  70");
+        assert_eq!(line1_at(6), "LocPing1.2ms Vol  70");
+    }
+}
@@ -0,0 +1,273 @@
+//! Shutting down or rebooting the computer from the screen, so that nobody needs to pull the plug & corrupt the SD card.
+//! Once the command has been run, the system stops the driver with SIGTERM as it shuts down. The driver then writes
+//! "Safe to unplug", which stays on the screen after the Pi has stopped, as the screen keeps what was last written to it.
+
+use anyhow::Context;
+use serde::Deserialize;
+use std::time::Duration;
+use tokio::signal::unix::{signal, SignalKind};
+
+use crate::clock::Clock;
+use crate::lcd::{Frame, Lc, LineNum, NUM_CHARACTERS_PER_LINE};
+use crate::localisation::{text, MessageId};
+
+/// how often the time taken so far is updated while waiting for the computer to stop
+const PROGRESS_PERIOD: Duration = Duration::from_secs(1);
+/// how long a dry run pretends the computer takes to stop
+const DRY_RUN_TIME: Duration = Duration::from_secs(3);
+/// how long the problem is shown for if the command fails, before going back to what is playing
+const FAILURE_TIME: Duration = Duration::from_secs(10);
+
+/// PowerOff is the way the computer is stopped
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum PowerOff {
+    ShutDown,
+    Reboot,
+}
+
+impl PowerOff {
+    /// question returns the message asking whether the computer really is to be stopped
+    pub fn question(self) -> MessageId {
+        match self {
+            PowerOff::ShutDown => MessageId::ConfirmShutDown,
+            PowerOff::Reboot => MessageId::ConfirmReboot,
+        }
+    }
+
+    fn in_progress(self) -> MessageId {
+        match self {
+            PowerOff::ShutDown => MessageId::ShuttingDown,
+            PowerOff::Reboot => MessageId::Rebooting,
+        }
+    }
+
+    /// last_words returns the message left on the screen once the computer has stopped
+    fn last_words(self) -> MessageId {
+        match self {
+            PowerOff::ShutDown => MessageId::SafeToUnplug,
+            PowerOff::Reboot => MessageId::Rebooting,
+        }
+    }
+}
+
+/// ShutdownConfig holds the commands that shut down & reboot the computer, eg
+/// ```toml
+/// [shutdown]
+/// shutdown_command = [ "sudo", "systemctl", "poweroff" ]
+/// dry_run = true
+/// ```
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct ShutdownConfig {
+    pub shutdown_command: Vec<String>,
+    pub reboot_command: Vec<String>,
+    /// if true, the command is printed rather than run, & the rest is shown as if it had been, so that it can be tried out.
+    /// The driver then goes back to showing what is playing.
+    pub dry_run: bool,
+}
+
+impl Default for ShutdownConfig {
+    fn default() -> Self {
+        let command = |arguments: &[&str]| {
+            arguments
+                .iter()
+                .map(|argument| argument.to_string())
+                .collect()
+        };
+        ShutdownConfig {
+            shutdown_command: command(&["sudo", "shutdown", "-h", "now"]),
+            reboot_command: command(&["sudo", "shutdown", "-r", "now"]),
+            dry_run: false,
+        }
+    }
+}
+
+/// power_off runs the command to shut down or reboot the computer, showing the progress on the screen, & returns
+/// once the system has asked the driver to stop & the last frame has been shown. If the command fails,
+/// the problem is shown for a while & returned, so that the driver can go back to showing what is playing.
+/// The progress is timed by the driver's clock.
+pub async fn power_off(
+    lcd: &mut Lc,
+    config: &ShutdownConfig,
+    power_off: PowerOff,
+    clock: &dyn Clock,
+) -> anyhow::Result<()> {
+    // listen before running the command, as the signal can arrive before the command has finished
+    let mut terminate = signal(SignalKind::terminate()).context("Failed to listen for SIGTERM")?;
+    let command = match power_off {
+        PowerOff::ShutDown => &config.shutdown_command,
+        PowerOff::Reboot => &config.reboot_command,
+    };
+    let start = clock.now();
+    lcd.show_frame(&progress_frame(power_off, Duration::ZERO, config.dry_run));
+
+    if config.dry_run {
+        println!("Dry run, so not running {:?}", command);
+    } else if let Err(error) = run(command).await {
+        println!("{error:#}");
+        lcd.show_frame(&failure_frame(&error));
+        tokio::time::sleep(FAILURE_TIME).await;
+        // listening has stopped SIGTERM from stopping the driver, so it has to be done here
+        tokio::spawn(async move {
+            terminate.recv().await;
+            std::process::exit(0);
+        });
+        return Err(error);
+    }
+
+    let stopped = async {
+        if config.dry_run {
+            tokio::time::sleep(DRY_RUN_TIME).await;
+        } else {
+            terminate.recv().await;
+        }
+    };
+    tokio::pin!(stopped);
+    loop {
+        tokio::select! {
+            _ = &mut stopped => break,
+            _ = tokio::time::sleep(PROGRESS_PERIOD) => {
+                lcd.show_frame(&progress_frame(
+                    power_off,
+                    clock.now().saturating_duration_since(start),
+                    config.dry_run,
+                ));
+            }
+        }
+    }
+    println!("The computer is stopping");
+    let mut last_frame = Frame::new();
+    last_frame.write_multiline(
+        LineNum::Line2,
+        NUM_CHARACTERS_PER_LINE,
+        &text(power_off.last_words(), &[]),
+    );
+    if config.dry_run {
+        last_frame.write_multiline(
+            LineNum::Line4,
+            NUM_CHARACTERS_PER_LINE,
+            &text(MessageId::ShutdownDryRun, &[]),
+        );
+    }
+    lcd.show_frame(&last_frame);
+    Ok(())
+}
+
+/// run runs the command & waits for it to finish
+async fn run(command: &[String]) -> anyhow::Result<()> {
+    let (program, arguments) = command.split_first().context("The command is empty")?;
+    let status = tokio::process::Command::new(program)
+        .args(arguments)
+        .status()
+        .await
+        .with_context(|| format!("Failed to run {}", program))?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(anyhow::anyhow!(
+            "{} failed with {}",
+            command.join(" "),
+            status
+        ))
+    }
+}
+
+/// progress_frame shows that the computer is stopping, with a line of dots that grows while it is waited for,
+/// so that it is clear that something is happening
+fn progress_frame(power_off: PowerOff, elapsed: Duration, dry_run: bool) -> Frame {
+    let seconds = elapsed.as_secs();
+    let mut frame = Frame::new();
+    frame.write_multiline(
+        LineNum::Line1,
+        NUM_CHARACTERS_PER_LINE,
+        &text(power_off.in_progress(), &[]),
+    );
+    frame.write(
+        LineNum::Line2,
+        0,
+        &".".repeat(seconds as usize % NUM_CHARACTERS_PER_LINE + 1),
+    );
+    frame.write_multiline(
+        LineNum::Line3,
+        NUM_CHARACTERS_PER_LINE,
+        &text(MessageId::ShutdownElapsed, &[&seconds]),
+    );
+    if dry_run {
+        frame.write_multiline(
+            LineNum::Line4,
+            NUM_CHARACTERS_PER_LINE,
+            &text(MessageId::ShutdownDryRun, &[]),
+        );
+    }
+    frame
+}
+
+/// failure_frame shows that the computer could not be stopped, & why
+fn failure_frame(error: &anyhow::Error) -> Frame {
+    let mut frame = Frame::new();
+    frame.write_multiline(
+        LineNum::Line1,
+        NUM_CHARACTERS_PER_LINE,
+        &text(MessageId::ShutdownFailed, &[]),
+    );
+    frame.write_multiline(
+        LineNum::Line2,
+        NUM_CHARACTERS_PER_LINE * 3,
+        &format!("{error:#}"),
+    );
+    frame
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::SystemClock;
+    use tokio::time::Instant;
+
+    #[tokio::test(start_paused = true)]
+    async fn a_dry_run_ends_with_safe_to_unplug() {
+        let mut lcd = Lc::new_virtual(false);
+        let config = ShutdownConfig {
+            dry_run: true,
+            ..ShutdownConfig::default()
+        };
+        let start = Instant::now();
+        power_off(&mut lcd, &config, PowerOff::ShutDown, &SystemClock)
+            .await
+            .unwrap();
+        assert_eq!(start.elapsed(), DRY_RUN_TIME);
+        assert_eq!(
+            lcd.screen().line(LineNum::Line2).trim_end(),
+            "Safe to unplug"
+        );
+        assert_eq!(lcd.screen().line(LineNum::Line4).trim_end(), "Dry run");
+    }
+
+    #[test]
+    fn the_progress_is_shown() {
+        let frame = progress_frame(PowerOff::ShutDown, Duration::from_secs(2), false);
+        assert_eq!(frame.line(LineNum::Line1).trim_end(), "Shutting down...");
+        assert_eq!(frame.line(LineNum::Line2).trim_end(), "...");
+        assert_eq!(frame.line(LineNum::Line3).trim_end(), "2s so far");
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn a_failed_command_is_shown() {
+        let mut lcd = Lc::new_virtual(false);
+        let config = ShutdownConfig {
+            reboot_command: vec!["false".to_string()],
+            ..ShutdownConfig::default()
+        };
+        assert!(power_off(&mut lcd, &config, PowerOff::Reboot, &SystemClock)
+            .await
+            .is_err());
+        assert_eq!(
+            lcd.screen().line(LineNum::Line1).trim_end(),
+            "Could not shut down"
+        );
+        assert!(lcd
+            .screen()
+            .line(LineNum::Line2)
+            .starts_with("false failed with"));
+    }
+}
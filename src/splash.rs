@@ -0,0 +1,131 @@
+//! The splash shown for a few seconds when a station starts playing: the type of station on line 1, the channel in big digits
+//! on lines 2 & 3, & the title of the station on line 4. It is then wiped away from the top, one line at a time,
+//! to show what is playing underneath.
+
+use serde::Deserialize;
+use std::time::Duration;
+use tokio::time::Instant;
+
+use crate::compositor::{Compositor, Layer, LayerId, Priority};
+use crate::lcd::big_digits::{big_number, big_number_width};
+use crate::lcd::{LineNum, NUM_CHARACTERS_PER_LINE};
+use crate::player_model::PlayerModel;
+use crate::render::station_label;
+
+/// how long after one line of the splash goes the line below it goes
+const WIPE_STEP: Duration = Duration::from_millis(150);
+
+/// SplashConfig is the configuration of the splash, eg
+/// ```toml
+/// [splash]
+/// duration_secs = 5
+/// ```
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct SplashConfig {
+    pub enabled: bool,
+    /// how long the splash is shown for before it starts to be wiped away
+    pub duration_secs: u64,
+}
+
+impl Default for SplashConfig {
+    fn default() -> Self {
+        SplashConfig {
+            enabled: true,
+            duration_secs: 3,
+        }
+    }
+}
+
+/// splash_lines returns the 4 lines of the splash for the station that is playing. If the channel is too long
+/// to fit in big digits, which it can be as rradio can send any string, lines 2 & 3 are left blank.
+fn splash_lines(model: &PlayerModel) -> [String; 4] {
+    let digit_count = model.channel.chars().filter(char::is_ascii_digit).count();
+    let [top_half, bottom_half] = if big_number_width(digit_count) <= NUM_CHARACTERS_PER_LINE {
+        big_number(&model.channel) // empty if there is no channel number, eg for a podcast
+    } else {
+        [String::new(), String::new()]
+    };
+    let indent =
+        " ".repeat(NUM_CHARACTERS_PER_LINE.saturating_sub(big_number_width(digit_count)) / 2);
+    [
+        station_label(model),
+        format!("{}{}", indent, top_half),
+        format!("{}{}", indent, bottom_half),
+        model.station_title.clone(),
+    ]
+}
+
+/// show_splash puts the splash for the station that has just started playing into the compositor, with a layer for each line,
+/// so that each line can go a moment after the one above it
+pub fn show_splash(
+    compositor: &mut Compositor,
+    model: &PlayerModel,
+    config: &SplashConfig,
+    now: Instant,
+) {
+    let until = now + Duration::from_secs(config.duration_secs);
+    for ((index, line_number), line) in (0..)
+        .zip(LineNum::ALL.iter())
+        .zip(splash_lines(model).iter())
+    {
+        let mut layer = Layer::new();
+        layer.write_multiline(*line_number, NUM_CHARACTERS_PER_LINE, line);
+        compositor.set(
+            LayerId::Splash(index),
+            Priority::Splash,
+            layer,
+            Some(until + WIPE_STEP * u32::from(index)),
+        );
+    }
+}
+
+/// hide_splash removes the splash straight away, eg because another station has been asked for
+pub fn hide_splash(compositor: &mut Compositor) {
+    compositor.remove_if(|id| matches!(id, LayerId::Splash(_)));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rradio_messages::StationType;
+
+    #[test]
+    fn the_splash_is_wiped_away_from_the_top() {
+        let now = Instant::now();
+        let mut model = PlayerModel::default();
+        model.station_type = StationType::UrlList;
+        model.channel = "07".to_string();
+        model.station_title = "Jazz FM".to_string();
+        let mut compositor = Compositor::default();
+        show_splash(&mut compositor, &model, &SplashConfig::default(), now);
+
+        let frame = compositor.compose(now);
+        assert_eq!(frame.line(LineNum::Line1).trim_end(), "Station 07");
+        assert_eq!(frame.line(LineNum::Line2), "      █▀█ ▀▀█       ");
+        assert_eq!(frame.line(LineNum::Line3), "      █▄█   █       ");
+        assert_eq!(frame.line(LineNum::Line4).trim_end(), "Jazz FM");
+
+        let frame = compositor.compose(now + Duration::from_secs(3) + WIPE_STEP);
+        assert_eq!(frame.line(LineNum::Line2).trim_end(), "");
+        assert_eq!(frame.line(LineNum::Line3), "      █▄█   █       ");
+
+        hide_splash(&mut compositor);
+        assert_eq!(compositor.next_expiry(), None);
+    }
+
+    #[test]
+    fn a_channel_too_long_for_big_digits_is_only_shown_in_the_label() {
+        let now = Instant::now();
+        let mut model = PlayerModel::default();
+        model.station_type = StationType::UrlList;
+        model.channel = "123456".to_string();
+        let mut compositor = Compositor::default();
+        show_splash(&mut compositor, &model, &SplashConfig::default(), now);
+
+        let frame = compositor.compose(now);
+        assert_eq!(frame.line(LineNum::Line1).trim_end(), "Station 123456");
+        assert_eq!(frame.line(LineNum::Line2).trim_end(), "");
+        assert_eq!(frame.line(LineNum::Line3).trim_end(), "");
+    }
+}
@@ -0,0 +1,134 @@
+use std::collections::HashMap;
+
+use crate::config::StationNamesConfig;
+use crate::metadata::{RewriteRules, TrackMetadata};
+
+/// StationNames tidies up the names of stations before they are shown, eg by removing "QUIET: " from the start of the name,
+/// & holds the names that the config file says are to be shown instead of the station titles
+#[derive(Debug, Default)]
+pub struct StationNames {
+    rewrite: RewriteRules,
+    display_names: HashMap<String, String>, // keyed by the channel number without leading zeros
+}
+
+/// channel_key removes leading zeros so that channel "07" can be specified as "7" in the config file
+fn channel_key(channel: &str) -> String {
+    let trimmed = channel.trim().trim_start_matches('0');
+    if trimmed.is_empty() && !channel.trim().is_empty() {
+        "0".to_string()
+    } else {
+        trimmed.to_string()
+    }
+}
+
+impl StationNames {
+    pub fn new(config: &StationNamesConfig) -> Self {
+        StationNames {
+            rewrite: RewriteRules::new(&config.rewrite),
+            display_names: config
+                .display_names
+                .iter()
+                .map(|(channel, name)| (channel_key(channel), name.clone()))
+                .collect(),
+        }
+    }
+
+    /// clean applies the rewrite rules to the text & trims any white space left at either end
+    pub fn clean(&self, text: &str) -> String {
+        self.rewrite.apply(text).trim().to_string()
+    }
+
+    /// display_title returns the name to show for the station on the specified channel:
+    /// the name given in the config file if there is one, else the tidied station title
+    pub fn display_title(&self, channel: &str, station_title: &str) -> String {
+        match self.display_names.get(&channel_key(channel)) {
+            Some(display_name) => display_name.clone(),
+            None => self.clean(station_title),
+        }
+    }
+
+    /// named_channels returns the channels given names in the config file, in order, with their names, eg ("07", "Jazz FM")
+    pub fn named_channels(&self) -> Vec<(String, String)> {
+        let mut named_channels: Vec<(String, String)> = self
+            .display_names
+            .iter()
+            .map(|(channel, name)| match channel.parse::<u32>() {
+                Ok(number) => (format!("{:02}", number), name.clone()),
+                Err(_) => (channel.clone(), name.clone()),
+            })
+            .collect();
+        named_channels.sort();
+        named_channels
+    }
+
+    /// clean_tags applies the rewrite rules to each of the tags, as stations often put their name in them too
+    pub fn clean_tags(&self, tags: &TrackMetadata) -> TrackMetadata {
+        TrackMetadata {
+            title: self.clean(&tags.title),
+            artist: self.clean(&tags.artist),
+            album: self.clean(&tags.album),
+            organisation: self.clean(&tags.organisation),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::RewriteRule;
+
+    fn station_names(display_names: &[(&str, &str)]) -> StationNames {
+        let mut config = StationNamesConfig::default();
+        config.rewrite.push(RewriteRule::new(r" - Radio$", " R"));
+        config.rewrite.push(RewriteRule::new(r"(?i)^www\..*$", "")); // web addresses are of no use on the screen
+        config.display_names = display_names
+            .iter()
+            .map(|(channel, name)| (channel.to_string(), name.to_string()))
+            .collect();
+        StationNames::new(&config)
+    }
+
+    #[test]
+    fn channels_are_the_same_with_or_without_leading_zeros() {
+        assert_eq!(channel_key("07"), "7");
+        assert_eq!(channel_key(" 7 "), "7");
+        assert_eq!(channel_key("00"), "0");
+        assert_eq!(channel_key(""), "");
+
+        let names = station_names(&[("7", "Radio 4"), ("12", "Jazz FM")]);
+        assert_eq!(names.display_title("07", "BBC Radio 4 FM"), "Radio 4");
+        assert_eq!(names.display_title("7", "BBC Radio 4 FM"), "Radio 4");
+        assert_eq!(
+            names.named_channels(),
+            vec![
+                ("07".to_string(), "Radio 4".to_string()),
+                ("12".to_string(), "Jazz FM".to_string())
+            ]
+        );
+    }
+
+    #[test]
+    fn titles_are_rewritten() {
+        let names = station_names(&[]);
+        assert_eq!(
+            names.display_title("03", "QUIET: Classic - Radio"),
+            "Classic R"
+        );
+        assert_eq!(names.display_title("04", "Jazz FM (AAC)"), "Jazz FM");
+    }
+
+    #[test]
+    fn a_tag_that_is_only_a_web_address_is_discarded() {
+        let names = station_names(&[]);
+        let tags = TrackMetadata {
+            title: "So What".to_string(),
+            artist: "Miles Davis".to_string(),
+            album: String::new(),
+            organisation: "www.jazzfm.com".to_string(),
+        };
+        let cleaned = names.clean_tags(&tags);
+        assert_eq!(cleaned.organisation, "");
+        assert_eq!(cleaned.title, "So What");
+        assert_eq!(cleaned.artist, "Miles Davis");
+    }
+}
@@ -0,0 +1,231 @@
+use rradio_messages::{PingError, PingTarget, PingTimes, PipelineState};
+use std::time::Duration;
+
+use crate::localisation::{text, MessageId};
+
+/// StatusText is implemented by every status message that can be written to the screen.
+/// Each message can be written in several variants of differing length, so that the layout can
+/// pick the most informative variant that fits in the number of cells it has available.
+pub trait StatusText {
+    /// variants returns every way of writing the message, the most informative (& usually the longest) first
+    fn variants(&self) -> Vec<String>;
+
+    /// fit returns the first variant that is no longer than width characters.
+    /// If none of them fit, the last (& shortest) variant is truncated to the width.
+    fn fit(&self, width: usize) -> String {
+        let variants = self.variants();
+        match variants
+            .iter()
+            .find(|variant| variant.chars().count() <= width)
+        {
+            Some(variant) => variant.clone(),
+            None => variants
+                .last()
+                .map(|shortest| shortest.chars().take(width).collect())
+                .unwrap_or_default(),
+        }
+    }
+}
+
+/// target_names returns the long, 3 letter & 1 letter names used when writing a ping to the screen
+fn target_names(target: PingTarget) -> (String, String, &'static str) {
+    match target {
+        PingTarget::Gateway => (
+            text(MessageId::PingLocal, &[]),
+            text(MessageId::PingLocalShort, &[]),
+            "L",
+        ),
+        PingTarget::Remote => (
+            text(MessageId::PingRemote, &[]),
+            text(MessageId::PingRemoteShort, &[]),
+            "R",
+        ),
+    }
+}
+
+/// PingStatus is the result of the most recent ping, ie the one that should be shown on the screen
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum PingStatus {
+    Time {
+        target: PingTarget,
+        time: Duration,
+    },
+    Failed {
+        target: PingTarget,
+        error: PingError,
+    },
+    BadUrl,
+}
+
+impl PingStatus {
+    /// latest returns the status of the latest ping, or None if there has not been a ping
+    pub fn latest(ping_times: &PingTimes) -> Option<PingStatus> {
+        match ping_times {
+            PingTimes::None => None,
+            PingTimes::BadUrl => Some(PingStatus::BadUrl),
+            PingTimes::Gateway(Ok(gateway_ping))
+            | PingTimes::GatewayAndRemote {
+                gateway_ping, // this branch matches local pings that did not give an error
+                remote_ping: _,
+                latest: PingTarget::Gateway,
+            }
+            | PingTimes::FinishedPingingRemote { gateway_ping } => Some(PingStatus::Time {
+                target: PingTarget::Gateway,
+                time: *gateway_ping,
+            }),
+            PingTimes::Gateway(Err(gateway_error)) => Some(PingStatus::Failed {
+                target: PingTarget::Gateway,
+                error: *gateway_error,
+            }),
+            PingTimes::GatewayAndRemote {
+                gateway_ping: _, //this branch matches remote pings that did not give an error
+                remote_ping: Ok(remote_ping),
+                latest: PingTarget::Remote,
+            } => Some(PingStatus::Time {
+                target: PingTarget::Remote,
+                time: *remote_ping,
+            }),
+            PingTimes::GatewayAndRemote {
+                gateway_ping: _, //this branch matches remote pings that failed
+                remote_ping: Err(remote_error),
+                latest: PingTarget::Remote,
+            } => Some(PingStatus::Failed {
+                target: PingTarget::Remote,
+                error: *remote_error,
+            }),
+        }
+    }
+}
+
+impl StatusText for PingStatus {
+    fn variants(&self) -> Vec<String> {
+        match *self {
+            PingStatus::Time { target, time } => {
+                let (long_name, three_letter_name, short_name) = target_names(target);
+                let millisecs = time.as_micros() as f32 / 1000.0;
+                let short_time = if time.as_nanos() < 9_999_999 {
+                    format!("{:.1}", millisecs)
+                } else {
+                    format!("{:>3}", time.as_millis())
+                };
+                vec![
+                    text(
+                        MessageId::PingTime,
+                        &[&long_name, &format!("{:.1}", millisecs)],
+                    ),
+                    format!("{three_letter_name}Ping{short_time}ms"), // eg "LocPing1.2ms" or "RemPing 12ms"
+                    format!("{short_name}Ping{:.0}ms", millisecs),
+                ]
+            }
+            PingStatus::Failed { target, error } => {
+                let (long_name, three_letter_name, short_name) = target_names(target);
+                let (message_id, short_error) = match error {
+                    PingError::FailedToRecieveICMP => (MessageId::PingRxFail, "Ping Rx fail"), // OS raised error when receiving ICMP message
+                    PingError::DestinationUnreachable => {
+                        (MessageId::PingDestinationUnreachable, "Dest Unreach") // Ping response reported as "Destination Unreachable"
+                    }
+                    PingError::Timeout => (MessageId::PingNoReply, "Ping NoReply"),
+                    PingError::FailedToSendICMP => (MessageId::PingTxFail, "Ping Tx Fail"),
+                    PingError::Dns => (MessageId::PingDnsError, "Ping DNS err"),
+                };
+                vec![
+                    text(message_id, &[&long_name]),         // eg "Local ping: No reply"
+                    text(message_id, &[&three_letter_name]), // eg "Loc Dest Unreachable"
+                    format!("{short_name}{short_error}"),    // eg "LPing NoReply"
+                ]
+            }
+            PingStatus::BadUrl => vec![text(MessageId::BadUrl, &[])],
+        }
+    }
+}
+
+impl StatusText for PipelineState {
+    fn variants(&self) -> Vec<String> {
+        vec![self.to_string()] // if it does not fit, fit truncates it
+    }
+}
+
+/// VolumeStatus is what is shown in the volume field: the volume, "Muted" or the gstreamer state if not playing
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub struct VolumeStatus {
+    pub pipe_line_state: PipelineState,
+    pub is_muted: bool,
+    pub volume: i32,
+}
+
+impl VolumeStatus {
+    /// is_showing_volume is true if the volume (or "Muted") is shown, & false if the pipeline state is shown instead
+    pub fn is_showing_volume(&self) -> bool {
+        (self.pipe_line_state == PipelineState::Playing) && self.volume >= 0
+    }
+}
+
+impl StatusText for VolumeStatus {
+    fn variants(&self) -> Vec<String> {
+        if !self.is_showing_volume() {
+            self.pipe_line_state.variants()
+        } else if self.is_muted {
+            vec![text(MessageId::Muted, &[])]
+        } else {
+            vec![
+                text(MessageId::Volume, &[&self.volume]),
+                format!("Vol{:>4}", self.volume),
+                format!("V{}", self.volume),
+            ]
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_longest_variant_that_fits_is_chosen() {
+        let ping = PingStatus::Time {
+            target: PingTarget::Gateway,
+            time: Duration::from_micros(1234),
+        };
+        assert_eq!(ping.fit(20), "Local ping 1.2ms");
+        assert_eq!(ping.fit(13), "LocPing1.2ms");
+        assert_eq!(ping.fit(8), "LPing1ms");
+    }
+
+    #[test]
+    fn the_shortest_variant_is_cut_short_if_none_fit() {
+        let ping = PingStatus::Failed {
+            target: PingTarget::Remote,
+            error: PingError::Timeout,
+        };
+        assert_eq!(ping.fit(6), "RPing ");
+        assert_eq!(PingStatus::BadUrl.fit(0), "");
+    }
+
+    #[test]
+    fn the_volume_field_shows_muted_or_the_state_instead_of_the_volume() {
+        let mut volume_status = VolumeStatus {
+            pipe_line_state: PipelineState::Playing,
+            is_muted: false,
+            volume: 70,
+        };
+        assert_eq!(volume_status.fit(10), "Volume 70");
+        assert_eq!(volume_status.fit(7), "Vol  70");
+        assert_eq!(volume_status.fit(3), "V70");
+
+        volume_status.is_muted = true;
+        assert!(volume_status.is_showing_volume());
+        assert_eq!(volume_status.fit(7), "Muted");
+
+        volume_status.pipe_line_state = PipelineState::Paused;
+        assert!(!volume_status.is_showing_volume());
+        assert_eq!(volume_status.fit(20), PipelineState::Paused.to_string());
+        assert_eq!(
+            volume_status.fit(3),
+            PipelineState::Paused
+                .to_string()
+                .chars()
+                .take(3)
+                .collect::<String>()
+        );
+    }
+}
@@ -0,0 +1,160 @@
+use std::time::Duration;
+
+use crate::get_local_ip_address::get_local_ip_address;
+use crate::lcd::{Lc, LineNum, NUM_CHARACTERS_PER_LINE};
+use crate::localisation::{text, MessageId};
+
+/// how long we wait before the first attempt to reconnect to rradio
+const FIRST_RETRY_DELAY: Duration = Duration::from_secs(1);
+/// the longest we wait between attempts, so that the screen comes back soon after rradio does
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(30);
+
+/// Backoff works out how long to wait before trying to connect to rradio again. The wait doubles after each
+/// failed attempt, as rradio can take a while to restart, eg during an upgrade.
+#[derive(Debug)]
+pub struct Backoff {
+    delay: Duration,
+    attempts: u32, // the number of attempts that have failed since the last time we connected
+}
+
+impl Default for Backoff {
+    fn default() -> Self {
+        Backoff {
+            delay: FIRST_RETRY_DELAY,
+            attempts: 0,
+        }
+    }
+}
+
+impl Backoff {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// next_delay returns how long to wait before the next attempt, & doubles the wait for the attempt after that
+    pub fn next_delay(&mut self) -> Duration {
+        let delay = self.delay;
+        self.delay = (self.delay * 2).min(MAX_RETRY_DELAY);
+        self.attempts += 1;
+        delay
+    }
+
+    /// attempts returns the number of failed attempts since we last connected
+    pub fn attempts(&self) -> u32 {
+        self.attempts
+    }
+
+    /// reset is called when we have connected, so that next time the connection is lost we retry quickly
+    pub fn reset(&mut self) {
+        *self = Backoff::new();
+    }
+}
+
+/// show_reconnecting shows that the connection to rradio has been lost & when we will try again
+pub fn show_reconnecting(lcd: &mut Lc, attempt: u32, delay: Duration) {
+    lcd.clear();
+    lcd.write_ascii(LineNum::Line1, 0, get_local_ip_address().as_str());
+    lcd.write_multiline(
+        LineNum::Line2,
+        NUM_CHARACTERS_PER_LINE,
+        &text(MessageId::Reconnecting, &[]),
+    );
+    lcd.write_multiline(
+        LineNum::Line3,
+        NUM_CHARACTERS_PER_LINE,
+        &text(MessageId::RetryIn, &[&attempt, &delay.as_secs()]),
+    );
+    lcd.write_temperature_and_strength(LineNum::Line4);
+}
+
+/// show_header_error shows why the header sent by rradio was not accepted, eg because rradio is a different version
+pub fn show_header_error(lcd: &mut Lc, err: &rradio_messages::BadRRadioHeader) {
+    match err {
+        rradio_messages::BadRRadioHeader::FailedToReadHeader(_io_error) => {
+            lcd.write_multiline(
+                // it was a really bad error, so write it to the LCD screen.
+                // it was so bad we might as well write to the entire screen
+                LineNum::Line1,
+                NUM_CHARACTERS_PER_LINE * 4,
+                err.to_string().as_str(),
+            );
+            println!("{:?}", err.to_string().as_str());
+        }
+        rradio_messages::BadRRadioHeader::HeaderMismatch {
+            expected: _,
+            actual,
+        } => match std::str::from_utf8(&actual[..]) {
+            // the convert to UTF8 assumes that the characters are ASCII
+            Err(_) => {
+                lcd.write_multiline(
+                    // oops they were not ASCII so report the problem
+                    LineNum::Line1,
+                    NUM_CHARACTERS_PER_LINE,
+                    &text(MessageId::BadRRadioHeader, &[]),
+                );
+                println!("Bad RRadio Header");
+                lcd.write_multiline(
+                    LineNum::Line2,
+                    NUM_CHARACTERS_PER_LINE,
+                    &text(MessageId::NotUtf8, &[]),
+                );
+                lcd.write_multiline(
+                    LineNum::Line3,
+                    NUM_CHARACTERS_PER_LINE * 2,
+                    &rradio_messages::DisplayApiHeader(&actual[..]).to_string(),
+                );
+            }
+            Ok(actual_str) => match actual_str.strip_prefix("rradio-messages_") {
+                // it was ASCII; it should start with the string "rradio-messages_"
+                // so try & remove the string, which of course could fail
+                None => {
+                    // it did fail, so it could not have been a rradio message
+                    lcd.write_multiline(
+                        LineNum::Line1,
+                        NUM_CHARACTERS_PER_LINE,
+                        &text(MessageId::NotRRadio, &[]),
+                    );
+                    lcd.write_multiline(
+                        LineNum::Line2,
+                        NUM_CHARACTERS_PER_LINE * 3,
+                        &rradio_messages::DisplayApiHeader(&actual[..]).to_string(),
+                    );
+                }
+                Some(version) => {
+                    // the message was from rradio, but the version was wrong.
+                    lcd.write_multiline(
+                        LineNum::Line1,
+                        NUM_CHARACTERS_PER_LINE,
+                        &text(MessageId::VersionMismatch, &[]),
+                    );
+                    lcd.write_multiline(
+                        LineNum::Line2,
+                        NUM_CHARACTERS_PER_LINE,
+                        &text(MessageId::LcdDriverVersion, &[&rradio_messages::VERSION]),
+                    );
+                    lcd.write_multiline(
+                        LineNum::Line3,
+                        NUM_CHARACTERS_PER_LINE,
+                        &text(MessageId::RRadioVersion, &[&version.trim_end()]),
+                    );
+                }
+            },
+        },
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_delay_doubles_up_to_a_limit() {
+        let mut backoff = Backoff::new();
+        let delays: Vec<u64> = (0..7).map(|_| backoff.next_delay().as_secs()).collect();
+        assert_eq!(delays, vec![1, 2, 4, 8, 16, 30, 30]);
+        assert_eq!(backoff.attempts(), 7);
+
+        backoff.reset();
+        assert_eq!(backoff.next_delay(), FIRST_RETRY_DELAY);
+    }
+}
@@ -0,0 +1,101 @@
+use std::fs::File;
+use std::io::prelude::Read; //needed for .read_to_string
+use std::time::Duration;
+use tokio::sync::watch;
+
+/// how often the readings are taken
+const READINGS_PERIOD: Duration = Duration::from_secs(3);
+
+/// SystemReadings are the readings taken from this computer, rather than sent by rradio, that are shown on the screen
+#[derive(PartialEq, Debug, Clone, Default)]
+pub struct SystemReadings {
+    pub ip_address: String,
+    pub cpu_temperature: i32,
+    pub wifi_strength: String,
+    pub wifi_ssid: String,
+    pub load_average: String,
+    pub uptime: Option<Duration>,
+}
+
+impl SystemReadings {
+    /// read takes the readings. It reads several files & runs iwgetid, which can take a while, so it is only called by take_readings.
+    pub fn read() -> Self {
+        SystemReadings {
+            ip_address: crate::get_local_ip_address::get_local_ip_address(),
+            cpu_temperature: crate::lcd::get_temperature::get_cpu_temperature(),
+            wifi_strength: crate::lcd::get_wifi_strength::get_wifi_signal_strength(),
+            wifi_ssid: get_wifi_ssid(),
+            load_average: get_load_average(),
+            uptime: get_uptime(),
+        }
+    }
+}
+
+/// take_readings takes the readings every few seconds on a thread of their own, so that the screen is not held up while they are taken,
+/// & sends them to the driver, until the driver has gone
+pub async fn take_readings(readings: watch::Sender<SystemReadings>) {
+    loop {
+        match tokio::task::spawn_blocking(SystemReadings::read).await {
+            Ok(reading) => {
+                if readings.send(reading).is_err() {
+                    return;
+                }
+            }
+            Err(error) => println!("Failed to take the system readings: {error}"),
+        }
+        tokio::time::sleep(READINGS_PERIOD).await;
+    }
+}
+
+/// read_pseudo_file returns the contents of a file such as /proc/uptime, or None if it could not be read
+fn read_pseudo_file(path: &str) -> Option<String> {
+    let mut contents = String::new();
+    match File::open(path).and_then(|mut file| file.read_to_string(&mut contents)) {
+        Ok(_file_size) => Some(contents),
+        Err(error) => {
+            println!("Problem reading the pseudo-file {path}: {error}");
+            None
+        }
+    }
+}
+
+/// get_load_average returns the 1, 5 & 15 minute load averages separated by spaces, eg "0.12 0.30 0.25"
+pub fn get_load_average() -> String {
+    match read_pseudo_file("/proc/loadavg") {
+        Some(load_average) => load_average
+            .split_whitespace()
+            .take(3)
+            .collect::<Vec<_>>()
+            .join(" "),
+        None => "er1".to_string(),
+    }
+}
+
+/// get_uptime returns how long the computer has been running
+pub fn get_uptime() -> Option<Duration> {
+    let uptime = read_pseudo_file("/proc/uptime")?; // contains the uptime in seconds followed by the idle time
+    let seconds = uptime.split_whitespace().next()?.parse::<f64>().ok()?;
+    Some(Duration::from_secs_f64(seconds))
+}
+
+/// get_wifi_ssid returns the name of the Wi-Fi network the computer is connected to, or an empty string if there is not one
+pub fn get_wifi_ssid() -> String {
+    match std::process::Command::new("iwgetid").arg("-r").output() {
+        Ok(output) => String::from_utf8_lossy(&output.stdout).trim().to_string(),
+        Err(error) => {
+            println!("Failed to run iwgetid to get the Wi-Fi SSID: {error}");
+            String::new()
+        }
+    }
+}
+
+/// format_uptime formats the uptime as days, hours & minutes eg "3d 04:12"
+pub fn format_uptime(uptime: Duration) -> String {
+    let minutes = uptime.as_secs() / 60;
+    let (days, hours, minutes) = (minutes / (24 * 60), (minutes / 60) % 24, minutes % 60);
+    if days > 0 {
+        format!("{days}d {hours:02}:{minutes:02}")
+    } else {
+        format!("{hours:02}:{minutes:02}")
+    }
+}
@@ -0,0 +1,178 @@
+//! The harness for the end to end tests. It starts the screen driver against a fake rradio listening on a Unix socket,
+//! shows the screen on a virtual LCD, & runs with a paused clock, so that the tests control the time & do not need an LCD screen.
+//! The date & time shown start just before the clocks go forward for summer time in the UK, so that the change can be tested.
+
+use chrono::{FixedOffset, TimeZone, Utc};
+use rradio_messages::Command;
+use std::future::Future;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+use tokio::io::{AsyncWriteExt, BufReader};
+
+use rradio_lcd_driver::clock::ManualClock;
+use rradio_lcd_driver::commands::CommandSender;
+use rradio_lcd_driver::config::Config;
+use rradio_lcd_driver::control::{Control, Request, Response};
+use rradio_lcd_driver::driver::{Driver, Ending};
+use rradio_lcd_driver::endpoint::{Endpoint, RRadioListener, RRadioStream};
+use rradio_lcd_driver::fake_rradio::{read_command, Header, Step};
+use rradio_lcd_driver::input::{Action, ActionSender};
+use rradio_lcd_driver::lcd::{Frame, Lc, LineNum, SharedScreen};
+
+/// how long the driver is given to show a step. As the clock is paused, this does not slow the tests down.
+const SETTLE_TIME: Duration = Duration::from_millis(10);
+
+/// uk_clock returns a clock that starts at 00:59:30 GMT on the 29th of March 2026, 30 seconds before the clocks go forward to BST
+pub fn uk_clock() -> ManualClock {
+    let hour = 3600;
+    ManualClock::new(
+        FixedOffset::east_opt(0)
+            .unwrap()
+            .with_ymd_and_hms(2026, 3, 29, 0, 59, 30)
+            .unwrap(),
+    )
+    .with_offset_from(
+        Utc.with_ymd_and_hms(2026, 3, 29, 1, 0, 0).unwrap(),
+        FixedOffset::east_opt(hour).unwrap(),
+    )
+}
+
+/// run runs a test with the clock paused. The clock only moves when the test waits, & then moves straight on to the next timer,
+/// so a test that waits for minutes takes no time at all. The driver runs as a local task, so it does not have to be Send.
+pub fn run(test: impl Future<Output = ()>) {
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .start_paused(true)
+        .build()
+        .unwrap();
+    tokio::task::LocalSet::new().block_on(&runtime, test);
+}
+
+/// Harness is one connection from the screen driver to the fake rradio
+pub struct Harness {
+    rradio: BufReader<RRadioStream>, // buffered, to read the commands from the driver
+    screen: SharedScreen,
+    commands: CommandSender,
+    actions: ActionSender,
+    control: Control,
+    socket_path: PathBuf,
+    driver: tokio::task::JoinHandle<Result<Ending, rradio_messages::BadRRadioHeader>>,
+}
+
+impl Harness {
+    /// start starts the driver with the default config, except that there is no splash when a station starts playing,
+    /// as it would hide what is playing, & connects it to a fake rradio that sends the correct header
+    pub async fn start() -> Harness {
+        let mut config = Config::default();
+        config.splash.enabled = false;
+        Harness::start_with(config, Header::Good).await
+    }
+
+    /// start_with starts the driver with the config given, & connects it to a fake rradio that sends the header given
+    pub async fn start_with(config: Config, header: Header) -> Harness {
+        static NEXT_SOCKET: AtomicUsize = AtomicUsize::new(0); // the tests in a file run in parallel, so each needs its own socket
+        let socket_path = std::env::temp_dir().join(format!(
+            "rradio_lcd_harness_{}_{}.sock",
+            std::process::id(),
+            NEXT_SOCKET.fetch_add(1, Ordering::Relaxed)
+        ));
+        let endpoint = Endpoint::Unix(socket_path.clone());
+        let listener = RRadioListener::bind(&endpoint).await.unwrap();
+        let (rradio, connection) = tokio::join!(listener.accept(), endpoint.connect());
+        let mut rradio = BufReader::new(rradio.unwrap());
+        rradio.write_all(&header.bytes()).await.unwrap();
+
+        let mut lcd = Lc::new_virtual(false);
+        let screen = lcd.share_screen();
+        let mut driver = Driver::new(lcd, &config, Box::new(uk_clock()));
+        let commands = driver.command_sender();
+        let actions = driver.action_sender();
+        let control = driver.control();
+        let connection = connection.unwrap();
+        let driver =
+            tokio::task::spawn_local(async move { driver.show_connection(connection).await });
+
+        let harness = Harness {
+            rradio,
+            screen,
+            commands,
+            actions,
+            control,
+            socket_path,
+            driver,
+        };
+        harness.wait(SETTLE_TIME).await;
+        harness
+    }
+
+    /// step sends a step written in the same way as the steps in a scenario file, eg `volume = 70`,
+    /// & waits until the driver has shown it. The delay in the step is ignored; use wait instead.
+    pub async fn step(&mut self, step: &str) {
+        let step: Step = toml::from_str(step)
+            .unwrap_or_else(|error| panic!("{:?} is not a valid step: {}", step, error));
+        step.send(&mut self.rradio).await.unwrap();
+        self.wait(SETTLE_TIME).await;
+    }
+
+    /// send_command sends a command to rradio through the driver, as a button would
+    pub fn send_command(&self, command: Command) {
+        self.commands.send(command);
+    }
+
+    /// do_action does an action as a control would, eg a button, & waits until the driver has done it
+    pub async fn do_action(&self, action: Action) {
+        self.actions.send(action);
+        self.wait(SETTLE_TIME).await;
+    }
+
+    /// press_key does an action as a key on a remote or keypad would, echoing the name of the key, & waits until the driver has done it
+    pub async fn press_key(&self, action: Action, key_name: &str) {
+        self.actions.send_echoed(action, key_name.to_string());
+        self.wait(SETTLE_TIME).await;
+    }
+
+    /// request makes a request as if it had come through the control socket, & waits until the driver has shown the result
+    pub async fn request(&self, request: Request) -> Response {
+        let response = self.control.handle(request);
+        self.wait(SETTLE_TIME).await;
+        response
+    }
+
+    /// received_command returns the next command the fake rradio has received from the driver
+    pub async fn received_command(&mut self) -> Command {
+        tokio::time::timeout(SETTLE_TIME, read_command(&mut self.rradio))
+            .await
+            .expect("the driver did not send a command")
+            .unwrap()
+            .expect("the driver closed the connection")
+    }
+
+    /// wait moves the clock on, letting the driver scroll, rotate the pages & time out overlays as it would in real life
+    pub async fn wait(&self, duration: Duration) {
+        tokio::time::sleep(duration).await;
+    }
+
+    /// screen returns what is on the screen
+    pub fn screen(&self) -> Frame {
+        self.screen.lock().unwrap().clone()
+    }
+
+    /// line returns what is on one line of the screen
+    pub fn line(&self, line_number: LineNum) -> String {
+        self.screen().line(line_number)
+    }
+
+    /// close closes the connection, as rradio does when it shuts down, & returns what the driver thought of the header
+    pub async fn close(mut self) -> Result<Ending, rradio_messages::BadRRadioHeader> {
+        self.rradio.shutdown().await.unwrap();
+        (&mut self.driver).await.unwrap()
+    }
+}
+
+impl Drop for Harness {
+    fn drop(&mut self) {
+        self.driver.abort();
+        let _ = std::fs::remove_file(&self.socket_path);
+    }
+}
@@ -0,0 +1,451 @@
+//! End to end tests: each test connects the screen driver to a fake rradio, sends it steps & checks what is on the screen
+
+mod common;
+
+use common::{run, Harness};
+use rradio_lcd_driver::config::Config;
+use rradio_lcd_driver::control::{Message, Region, Request, Response};
+use rradio_lcd_driver::driver::Ending;
+use rradio_lcd_driver::fake_rradio::Header;
+use rradio_lcd_driver::input::Action;
+use rradio_lcd_driver::lcd::LineNum;
+use rradio_lcd_driver::localisation::{text, MessageId};
+use rradio_lcd_driver::render::{SCROLL_DELAY, SCROLL_PERIOD};
+use rradio_lcd_driver::shutdown::PowerOff;
+use rradio_messages::Command;
+use std::time::Duration;
+
+#[test]
+fn a_missing_station_is_shown_with_the_date() {
+    run(async {
+        let mut radio = Harness::start().await;
+        radio.step(r#"station_not_found = "42""#).await;
+        assert_eq!(radio.line(LineNum::Line1).trim_end(), "No station 42");
+        assert_eq!(radio.line(LineNum::Line3), "  29 Mar 26 00:59:30");
+    });
+}
+
+#[test]
+fn the_time_of_day_goes_forward_for_summer_time() {
+    run(async {
+        let mut radio = Harness::start().await;
+        radio.step(r#"station_not_found = "42""#).await;
+        radio.wait(Duration::from_secs(29)).await;
+        assert_eq!(radio.line(LineNum::Line3), "  29 Mar 26 00:59:59");
+        radio.wait(Duration::from_secs(2)).await;
+        assert_eq!(radio.line(LineNum::Line3), "  29 Mar 26 02:00:01");
+    });
+}
+
+#[test]
+fn asking_for_the_same_missing_station_twice_shows_the_version() {
+    run(async {
+        let mut radio = Harness::start().await;
+        radio.step(r#"station_not_found = "42""#).await;
+        assert!(!radio
+            .line(LineNum::Line2)
+            .contains(env!("CARGO_PKG_VERSION")));
+        radio.step(r#"station_not_found = "42""#).await;
+        assert_eq!(
+            radio.line(LineNum::Line2).trim_end(),
+            text(MessageId::LcdVersion, &[&env!("CARGO_PKG_VERSION")])
+        );
+    });
+}
+
+#[test]
+fn the_station_and_volume_are_shown() {
+    run(async {
+        let mut radio = Harness::start().await;
+        radio
+            .step(
+                r#"
+                pipeline_state = "Playing"
+                station = { index = "07", title = "Jazz FM" }
+                volume = 70
+                "#,
+            )
+            .await;
+        assert_eq!(radio.line(LineNum::Line1), "Station 07   Vol  70");
+        assert_eq!(radio.line(LineNum::Line2).trim_end(), "Jazz FM");
+
+        radio.step("volume = 75").await;
+        assert!(radio.line(LineNum::Line1).ends_with("Vol  75"));
+    });
+}
+
+#[test]
+fn the_time_through_a_track_goes_up_every_second() {
+    run(async {
+        let mut radio = Harness::start().await;
+        radio
+            .step(
+                r#"
+                pipeline_state = "Playing"
+                station = { index = "03", title = "Holiday songs", source_type = "Usb", tracks = 12 }
+                volume = 70
+                tags = { title = "Sunny day" }
+                track_index = 1
+                track_duration_secs = 200
+                track_position_secs = 83
+                "#,
+            )
+            .await;
+        assert_eq!(radio.line(LineNum::Line1), "01:23 -01:57 Vol  70");
+        assert!(radio.line(LineNum::Line4).starts_with(&"█".repeat(8)));
+
+        radio.wait(Duration::from_secs(2)).await; // rradio has not sent the position again
+        assert!(radio.line(LineNum::Line1).starts_with("01:25 -01:55"));
+
+        radio.step(r#"pipeline_state = "Paused""#).await;
+        radio.wait(Duration::from_secs(2)).await;
+        assert_eq!(radio.line(LineNum::Line1), "01:25 -01:55 Paused ");
+    });
+}
+
+#[test]
+fn a_new_station_is_shown_with_a_splash() {
+    run(async {
+        let mut radio = Harness::start_with(Config::default(), Header::Good).await;
+        radio
+            .step(
+                r#"
+                pipeline_state = "Playing"
+                station = { index = "07", title = "Jazz FM" }
+                volume = 70
+                "#,
+            )
+            .await;
+        assert_eq!(radio.line(LineNum::Line1).trim_end(), "Station 07");
+        assert_eq!(radio.line(LineNum::Line3), "      █▄█   █       ");
+        assert_eq!(radio.line(LineNum::Line4).trim_end(), "Jazz FM");
+
+        radio.wait(Duration::from_secs(4)).await; // 3 seconds, & then wiped away a line at a time
+        assert_eq!(radio.line(LineNum::Line1), "Station 07   Vol  70");
+        assert_eq!(radio.line(LineNum::Line2).trim_end(), "Jazz FM");
+    });
+}
+
+#[test]
+fn a_change_of_volume_is_shown_big_for_a_while() {
+    run(async {
+        let mut radio = Harness::start().await;
+        radio
+            .step(
+                r#"
+                pipeline_state = "Playing"
+                station = { index = "07", title = "Jazz FM" }
+                volume = 70
+                "#,
+            )
+            .await;
+        assert_eq!(radio.line(LineNum::Line2).trim_end(), "Jazz FM"); // not when rradio first says what the volume is
+
+        radio.step("volume = 75").await;
+        assert_eq!(radio.line(LineNum::Line2), "Volume       ▀▀█ █≡≡");
+        assert_eq!(radio.line(LineNum::Line4), "█".repeat(15) + "     ");
+
+        radio.wait(Duration::from_secs(2)).await;
+        assert_eq!(radio.line(LineNum::Line2).trim_end(), "Jazz FM");
+    });
+}
+
+#[test]
+fn a_long_title_scrolls() {
+    run(async {
+        let mut radio = Harness::start().await;
+        radio
+            .step(
+                r#"
+                pipeline_state = "Playing"
+                station = { index = "07", title = "Jazz FM" }
+                tags = { title = "A very long title that will need to scroll across the screen", artist = "Someone" }
+                "#,
+            )
+            .await;
+        assert_eq!(radio.line(LineNum::Line2).trim_end(), "Jazz FM / Someone");
+        assert_eq!(radio.line(LineNum::Line3), "A very long title th");
+
+        radio.wait(SCROLL_DELAY).await; // the title is shown from the start for a while before it scrolls
+        assert_eq!(radio.line(LineNum::Line3), "A very long title th");
+        radio.wait(SCROLL_PERIOD).await;
+        assert_eq!(radio.line(LineNum::Line3), "long title that will");
+    });
+}
+
+#[test]
+fn a_cd_error_fills_the_screen() {
+    run(async {
+        let mut radio = Harness::start().await;
+        radio.step(r#"cd_error = "CdTrayIsOpen""#).await;
+        assert_eq!(radio.line(LineNum::Line1).trim_end(), "CD tray open");
+        assert_eq!(radio.line(LineNum::Line3).trim_end(), "Close the CD tray");
+    });
+}
+
+#[test]
+fn an_error_is_not_hidden_by_the_other_pages() {
+    run(async {
+        let mut radio = Harness::start().await;
+        radio
+            .step(
+                r#"
+                pipeline_state = "Playing"
+                station = { index = "07", title = "Jazz FM" }
+                "#,
+            )
+            .await;
+        radio
+            .step(
+                r#"
+                pipeline_state = "Null"
+                cd_error = "CdTrayIsOpen"
+                "#,
+            )
+            .await;
+        for _ in 0..9 {
+            // more than one full rotation of the pages, which takes 35 seconds
+            radio.wait(Duration::from_secs(5)).await;
+            assert_eq!(radio.line(LineNum::Line1).trim_end(), "CD tray open");
+        }
+    });
+}
+
+#[test]
+fn an_undecodable_message_is_shown_for_a_while() {
+    run(async {
+        let mut radio = Harness::start().await;
+        radio.step(r#"cd_error = "CdTrayIsOpen""#).await;
+        radio.step("send_garbage = true").await;
+        assert_eq!(
+            format!(
+                "{}{}",
+                radio.line(LineNum::Line3),
+                radio.line(LineNum::Line4)
+            )
+            .trim_end(),
+            text(MessageId::EventUndecodable, &[])
+        );
+
+        radio.wait(Duration::from_secs(7)).await; // the overlay is shown for 5 seconds by default, & goes at the next redraw
+        assert_eq!(radio.line(LineNum::Line3).trim_end(), "Close the CD tray");
+    });
+}
+
+#[test]
+fn a_header_from_a_different_version_is_reported() {
+    run(async {
+        let radio = Harness::start_with(Config::default(), Header::WrongVersion).await;
+        assert_eq!(
+            radio.line(LineNum::Line1).trim_end(),
+            text(MessageId::VersionMismatch, &[])
+        );
+        assert!(radio.close().await.is_err());
+    });
+}
+
+#[test]
+fn the_driver_stops_when_rradio_closes_the_connection() {
+    run(async {
+        let mut radio = Harness::start().await;
+        radio.step("volume = 70").await;
+        let closed_at = tokio::time::Instant::now();
+        assert_eq!(radio.close().await.ok(), Some(Ending::ConnectionClosed));
+        // the driver does not wait for the overlay saying the connection closed to expire
+        assert!(closed_at.elapsed() < Duration::from_secs(1));
+    });
+}
+
+#[test]
+fn a_connection_closed_straight_after_the_header_is_not_counted_as_working() {
+    run(async {
+        let radio = Harness::start().await;
+        assert_eq!(radio.close().await.ok(), Some(Ending::ClosedWithoutEvents));
+    });
+}
+
+#[test]
+fn commands_are_sent_to_rradio() {
+    run(async {
+        let mut radio = Harness::start().await;
+        radio.send_command(Command::SetChannel("07".to_string()));
+        radio.send_command(Command::VolumeUp);
+        assert!(matches!(
+            radio.received_command().await,
+            Command::SetChannel(channel) if channel == "07"
+        ));
+        assert!(matches!(radio.received_command().await, Command::VolumeUp));
+    });
+}
+
+#[test]
+fn a_button_can_change_the_station_and_the_page() {
+    run(async {
+        let mut radio = Harness::start().await;
+        radio
+            .step(
+                r#"
+                pipeline_state = "Playing"
+                station = { index = "07", title = "Jazz FM" }
+                "#,
+            )
+            .await;
+        radio.do_action(Action::StationUp).await;
+        assert!(matches!(
+            radio.received_command().await,
+            Command::SetChannel(channel) if channel == "08"
+        ));
+
+        radio.do_action(Action::NextPage).await;
+        assert_eq!(radio.line(LineNum::Line2).trim_end(), "WiFi"); // the network page, even though it is playing
+    });
+}
+
+#[test]
+fn a_channel_can_be_typed_on_a_remote() {
+    run(async {
+        let mut radio = Harness::start().await;
+        radio
+            .step(
+                r#"
+                pipeline_state = "Playing"
+                station = { index = "07", title = "Jazz FM" }
+                "#,
+            )
+            .await;
+        radio.press_key(Action::VolumeUp, "VOLUMEUP").await;
+        assert!(matches!(radio.received_command().await, Command::VolumeUp));
+        assert_eq!(radio.line(LineNum::Line3).trim_end(), "Key VOLUMEUP");
+
+        radio.press_key(Action::Digit(4), "KP4").await;
+        assert_eq!(radio.line(LineNum::Line3).trim_end(), "Station 4_");
+        radio.press_key(Action::Digit(2), "KP2").await;
+        assert!(matches!(
+            radio.received_command().await,
+            Command::SetChannel(channel) if channel == "42"
+        ));
+        assert_eq!(radio.line(LineNum::Line3).trim_end(), "Station 42");
+
+        radio.wait(Duration::from_secs(2)).await;
+        assert_ne!(radio.line(LineNum::Line3).trim_end(), "Station 42"); // the echo has gone
+    });
+}
+
+#[test]
+fn a_station_can_be_chosen_from_the_menu() {
+    run(async {
+        let config: Config = toml::from_str(
+            r#"
+            [station_names.display_names]
+            "3" = "Radio 3"
+            "12" = "Jazz FM"
+            "#,
+        )
+        .unwrap();
+        let mut radio = Harness::start_with(config, Header::Good).await;
+        radio.step(r#"pipeline_state = "Playing""#).await;
+        radio.do_action(Action::Menu).await;
+        assert_eq!(radio.line(LineNum::Line1), "Menu             1/8");
+
+        radio.do_action(Action::NextPage).await; // select the stations
+        radio.do_action(Action::VolumeUp).await; // turning the knob moves down
+        assert_eq!(radio.line(LineNum::Line3).trim_end(), ">12 Jazz FM");
+        radio.do_action(Action::NextPage).await;
+        assert!(matches!(
+            radio.received_command().await,
+            Command::SetChannel(channel) if channel == "12"
+        ));
+        assert_ne!(radio.line(LineNum::Line1), "Stations         2/2"); // the menu has closed
+    });
+}
+
+#[test]
+fn the_menu_times_out() {
+    run(async {
+        let radio = Harness::start().await;
+        radio.do_action(Action::Menu).await;
+        assert_eq!(radio.line(LineNum::Line1), "Menu             1/8");
+        radio.wait(Duration::from_secs(31)).await;
+        assert_ne!(radio.line(LineNum::Line1), "Menu             1/8");
+    });
+}
+
+#[test]
+fn reconnect_can_be_chosen_from_the_menu() {
+    run(async {
+        let radio = Harness::start().await;
+        radio.do_action(Action::Menu).await;
+        radio.do_action(Action::StationUp).await; // goes round to shut down at the bottom
+        radio.do_action(Action::StationUp).await;
+        radio.do_action(Action::StationUp).await;
+        radio.do_action(Action::StationUp).await;
+        assert_eq!(radio.line(LineNum::Line2).trim_end(), ">Reconnect");
+        radio.do_action(Action::NextPage).await;
+        assert_eq!(radio.close().await.ok(), Some(Ending::Reconnect));
+    });
+}
+
+#[test]
+fn shutting_down_is_confirmed_on_the_screen() {
+    run(async {
+        let radio = Harness::start().await;
+        radio.do_action(Action::ShutDown).await;
+        assert_eq!(radio.line(LineNum::Line1), "Shut down?       1/2");
+        radio.do_action(Action::VolumeUp).await;
+        radio.do_action(Action::NextPage).await; // yes
+        assert_eq!(
+            radio.close().await.ok(),
+            Some(Ending::PowerOff(PowerOff::ShutDown))
+        );
+    });
+}
+
+#[test]
+fn shutting_down_through_the_control_socket_is_confirmed_on_the_screen() {
+    run(async {
+        let radio = Harness::start().await;
+        assert_eq!(radio.request(Request::ShutDown).await, Response::Asked);
+        assert_eq!(radio.line(LineNum::Line1), "Shut down?       1/2");
+        radio.do_action(Action::NextPage).await; // no
+        assert_ne!(radio.line(LineNum::Line1), "Shut down?       1/2");
+        assert_eq!(radio.close().await.ok(), Some(Ending::ClosedWithoutEvents)); // still running
+    });
+}
+
+#[test]
+fn rebooting_through_the_control_socket_is_confirmed_on_the_screen() {
+    run(async {
+        let radio = Harness::start().await;
+        assert_eq!(radio.request(Request::Reboot).await, Response::Asked);
+        assert_eq!(radio.line(LineNum::Line1), "Reboot?          1/2");
+        radio.do_action(Action::VolumeUp).await;
+        radio.do_action(Action::NextPage).await; // yes
+        assert_eq!(
+            radio.close().await.ok(),
+            Some(Ending::PowerOff(PowerOff::Reboot))
+        );
+    });
+}
+
+#[test]
+fn a_message_can_be_posted_through_the_control_socket() {
+    run(async {
+        let radio = Harness::start().await;
+        let response = radio
+            .request(Request::Post(Message {
+                text: "Backing up".to_string(),
+                priority: 5,
+                duration_secs: 20,
+                region: Region::Line3,
+            }))
+            .await;
+        assert_eq!(response, Response::Posted { id: 1 });
+        assert_eq!(radio.line(LineNum::Line3).trim_end(), "Backing up");
+        match radio.request(Request::Frame).await {
+            Response::Frame { lines } => assert_eq!(lines[2].trim_end(), "Backing up"),
+            other => panic!("expected the frame, got {:?}", other),
+        }
+        radio.wait(Duration::from_secs(22)).await; // goes at the next redraw after it expires
+        assert_ne!(radio.line(LineNum::Line3).trim_end(), "Backing up");
+    });
+}